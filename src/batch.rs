@@ -0,0 +1,437 @@
+use crate::completion::{ProviderResponse, StructuredOutput};
+use crate::config::Config;
+use crate::gen::Generator;
+use crate::input::Input;
+use futures::stream::{self, StreamExt};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+/// One character of a batch manifest.
+#[derive(Deserialize, Debug, Clone)]
+pub struct BatchEntry {
+    pub name: String,
+    #[serde(default)]
+    pub facts: Vec<String>,
+    #[serde(default)]
+    pub files: Vec<String>,
+    /// File name under the characters directory, derived from `name` when missing.
+    #[serde(default)]
+    pub output: Option<String>,
+    /// Direction for the generation, [`Batch::DEFAULT_INSTRUCTION`] when missing.
+    #[serde(default)]
+    pub instruction: Option<String>,
+}
+
+impl BatchEntry {
+    /// File name the character is saved as. `output` must be a plain file name, it cannot point
+    /// outside the characters directory.
+    pub fn output_file_name(&self) -> Result<String, anyhow::Error> {
+        match &self.output {
+            Some(output) => {
+                let stem = output.strip_suffix(".json").unwrap_or(output);
+                if stem.is_empty() || stem == "." || stem == ".." || stem.contains(['/', '\\']) {
+                    return Err(anyhow::anyhow!(
+                        "Output `{}` of {} must be a file name without path separators",
+                        output,
+                        self.name
+                    ));
+                }
+                Ok(format!("{}.json", stem))
+            }
+            None => {
+                let slug: String = self
+                    .name
+                    .to_lowercase()
+                    .chars()
+                    .map(|c| if c.is_alphanumeric() { c } else { '-' })
+                    .collect();
+                let slug: Vec<&str> = slug.split('-').filter(|s| !s.is_empty()).collect();
+                if slug.is_empty() {
+                    return Err(anyhow::anyhow!(
+                        "Name `{}` has no letters or digits to derive a file name from, set `output`",
+                        self.name
+                    ));
+                }
+                Ok(format!("{}.json", slug.join("-")))
+            }
+        }
+    }
+
+    fn input(&self) -> Input {
+        Input {
+            name: self.name.clone(),
+            facts: self.facts.clone(),
+            files: self.files.clone(),
+        }
+    }
+}
+
+/// CSV row of a manifest, `facts` and `files` hold `|` separated values.
+#[derive(Deserialize)]
+struct CsvEntry {
+    name: String,
+    #[serde(default)]
+    facts: String,
+    #[serde(default)]
+    files: String,
+    #[serde(default)]
+    output: String,
+    #[serde(default)]
+    instruction: String,
+}
+
+impl From<CsvEntry> for BatchEntry {
+    fn from(row: CsvEntry) -> Self {
+        let split = |values: &str| -> Vec<String> {
+            values
+                .split('|')
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+                .collect()
+        };
+        let optional = |value: String| match value.trim().is_empty() {
+            true => None,
+            false => Some(value.trim().to_string()),
+        };
+        BatchEntry {
+            name: row.name,
+            facts: split(&row.facts),
+            files: split(&row.files),
+            output: optional(row.output),
+            instruction: optional(row.instruction),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchStatus {
+    Succeeded,
+    Failed,
+}
+
+/// Outcome of one entry, keyed by its output file name in the [`BatchReport`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BatchResult {
+    pub name: String,
+    pub status: BatchStatus,
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub error: Option<String>,
+    pub finished_at: String,
+}
+
+/// Progress of a batch, rewritten after every entry so an interrupted run can resume.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct BatchReport {
+    pub results: BTreeMap<String, BatchResult>,
+}
+
+impl BatchReport {
+    pub fn load(path: &str) -> Result<Self, anyhow::Error> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), anyhow::Error> {
+        if let Some(parent) = Path::new(path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Whether the entry saved as `output` succeeded and its character is still there.
+    pub fn is_done(&self, output: &str) -> bool {
+        self.results.get(output).is_some_and(|result| {
+            result.status == BatchStatus::Succeeded
+                && result
+                    .path
+                    .as_ref()
+                    .is_some_and(|path| Path::new(path).exists())
+        })
+    }
+
+    pub fn count(&self, status: BatchStatus) -> usize {
+        self.results
+            .values()
+            .filter(|result| result.status == status)
+            .count()
+    }
+}
+
+/// Generates many characters from a manifest with at most `concurrency` generations in flight.
+pub struct Batch {
+    pub entries: Vec<BatchEntry>,
+    pub concurrency: usize,
+    /// Where the [`BatchReport`] is kept, entries that already succeeded in it are skipped.
+    pub report_path: String,
+}
+
+impl Batch {
+    pub const DEFAULT_INSTRUCTION: &'static str =
+        "Create a compelling, original character that stays true to the facts.";
+
+    /// Reads a manifest, either a JSON array of entries or a CSV file with a header row.
+    pub fn load_manifest(path: &str) -> Result<Vec<BatchEntry>, anyhow::Error> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read manifest {}: {}", path, e))?;
+        let entries: Vec<BatchEntry> = if path.ends_with(".csv") {
+            csv::Reader::from_reader(content.as_bytes())
+                .deserialize::<CsvEntry>()
+                .map(|row| row.map(BatchEntry::from))
+                .collect::<Result<_, _>>()
+                .map_err(|e| anyhow::anyhow!("Invalid manifest {}: {}", path, e))?
+        } else {
+            serde_json::from_str(&content)
+                .map_err(|e| anyhow::anyhow!("Invalid manifest {}: {}", path, e))?
+        };
+
+        let mut outputs = HashSet::new();
+        for entry in entries.iter() {
+            let output = entry
+                .output_file_name()
+                .map_err(|e| anyhow::anyhow!("Invalid manifest {}: {}", path, e))?;
+            if !outputs.insert(output.clone()) {
+                return Err(anyhow::anyhow!(
+                    "Manifest {} writes `{}` more than once",
+                    path,
+                    output
+                ));
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Runs every pending entry and returns the updated report. Failures are recorded rather than
+    /// stopping the batch.
+    pub async fn run<CM>(&self, config: &Config, completion_model: CM) -> BatchReport
+    where
+        CM: rig::completion::CompletionModel<Response = ProviderResponse>
+            + StructuredOutput
+            + Clone,
+    {
+        let mut report = BatchReport::load(&self.report_path).unwrap_or_default();
+        let mut pending = vec![];
+        for entry in self.entries.iter() {
+            let output = match entry.output_file_name() {
+                Ok(output) => output,
+                Err(e) => {
+                    error!("[BATCH] Skipping {}: {}", entry.name, e);
+                    continue;
+                }
+            };
+            if report.is_done(&output) {
+                info!("[BATCH] Skipping {}, already generated", entry.name);
+                continue;
+            }
+            pending.push((entry, output));
+        }
+        info!(
+            "[BATCH] Generating {} of {} characters ({} at a time)",
+            pending.len(),
+            self.entries.len(),
+            self.concurrency
+        );
+
+        let mut results = stream::iter(pending)
+            .map(|(entry, output)| {
+                let mut config = config.clone();
+                config.output_file_name = output.clone();
+                let completion_model = completion_model.clone();
+                async move {
+                    let mut gen = Generator::new(config, entry.input(), completion_model);
+                    let instruction = entry
+                        .instruction
+                        .as_deref()
+                        .unwrap_or(Self::DEFAULT_INSTRUCTION);
+                    (entry, output, gen.create(instruction).await)
+                }
+            })
+            .buffer_unordered(self.concurrency.max(1));
+
+        while let Some((entry, output, result)) = results.next().await {
+            let finished_at = chrono::Local::now().to_rfc3339();
+            let result = match result {
+                Ok(character) => {
+                    info!("[BATCH] {} saved to {}", entry.name, character.path);
+                    BatchResult {
+                        name: entry.name.clone(),
+                        status: BatchStatus::Succeeded,
+                        path: Some(character.path),
+                        error: None,
+                        finished_at,
+                    }
+                }
+                Err(e) => {
+                    error!("[BATCH] {} failed: {}", entry.name, e);
+                    BatchResult {
+                        name: entry.name.clone(),
+                        status: BatchStatus::Failed,
+                        path: None,
+                        error: Some(e.to_string()),
+                        finished_at,
+                    }
+                }
+            };
+            report.results.insert(output, result);
+            if let Err(e) = report.save(&self.report_path) {
+                error!("[BATCH] Failed to save report: {}", e);
+            }
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, output: Option<&str>) -> BatchEntry {
+        BatchEntry {
+            name: name.to_string(),
+            facts: vec![],
+            files: vec![],
+            output: output.map(str::to_string),
+            instruction: None,
+        }
+    }
+
+    fn manifest(dir: &tempfile::TempDir, file_name: &str, content: &str) -> String {
+        let path = dir.path().join(file_name).to_string_lossy().into_owned();
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn parses_json_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = manifest(
+            &dir,
+            "cast.json",
+            r#"[
+                {"name": "Ayla", "facts": ["pilot"], "instruction": "make her daring"},
+                {"name": "Bo Jin", "files": ["bo.pdf"], "output": "bo"}
+            ]"#,
+        );
+        let entries = Batch::load_manifest(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].facts, vec!["pilot"]);
+        assert_eq!(entries[0].instruction.as_deref(), Some("make her daring"));
+        assert_eq!(entries[0].output, None);
+        assert_eq!(entries[1].files, vec!["bo.pdf"]);
+        assert_eq!(entries[1].output_file_name().unwrap(), "bo.json");
+    }
+
+    #[test]
+    fn parses_csv_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = manifest(
+            &dir,
+            "cast.csv",
+            "name,facts,files,output,instruction\n\
+             Ayla,pilot| afraid of heights |,,,make her daring\n\
+             Bo Jin,chef,a.txt|b.txt,bo.json, \n",
+        );
+        let entries = Batch::load_manifest(&path).unwrap();
+        assert_eq!(entries[0].facts, vec!["pilot", "afraid of heights"]);
+        assert!(entries[0].files.is_empty());
+        assert_eq!(entries[0].output, None);
+        assert_eq!(entries[0].instruction.as_deref(), Some("make her daring"));
+        assert_eq!(entries[1].files, vec!["a.txt", "b.txt"]);
+        assert_eq!(entries[1].output.as_deref(), Some("bo.json"));
+        assert_eq!(entries[1].instruction, None);
+    }
+
+    #[test]
+    fn rejects_duplicate_outputs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = manifest(
+            &dir,
+            "cast.json",
+            r#"[{"name": "Ayla"}, {"name": "Someone", "output": "ayla.json"}]"#,
+        );
+        let error = Batch::load_manifest(&path).unwrap_err().to_string();
+        assert!(
+            error.contains("writes `ayla.json` more than once"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn derives_file_names_from_names() {
+        let file_name = |name| entry(name, None).output_file_name().unwrap();
+        assert_eq!(file_name("Ayla"), "ayla.json");
+        assert_eq!(file_name("  Bo Jin!  "), "bo-jin.json");
+        assert_eq!(file_name("Dr. Émile -- Zola"), "dr-émile-zola.json");
+        assert!(entry("???", None).output_file_name().is_err());
+    }
+
+    #[test]
+    fn keeps_outputs_inside_the_characters_directory() {
+        assert_eq!(
+            entry("Ayla", Some("hero")).output_file_name().unwrap(),
+            "hero.json"
+        );
+        assert_eq!(
+            entry("Ayla", Some("hero.json")).output_file_name().unwrap(),
+            "hero.json"
+        );
+        for output in ["../../etc/x", "sub/hero", "..\\hero", "..", ".json", ""] {
+            assert!(
+                entry("Ayla", Some(output)).output_file_name().is_err(),
+                "{}",
+                output
+            );
+        }
+    }
+
+    #[test]
+    fn resumes_only_entries_still_to_do() {
+        let dir = tempfile::tempdir().unwrap();
+        let saved = manifest(&dir, "ayla.json", "{}");
+        let result = |status, path: Option<String>| BatchResult {
+            name: String::new(),
+            status,
+            path,
+            error: None,
+            finished_at: String::new(),
+        };
+
+        let mut report = BatchReport::default();
+        report.results.insert(
+            "ayla.json".to_string(),
+            result(BatchStatus::Succeeded, Some(saved)),
+        );
+        report.results.insert(
+            "bo.json".to_string(),
+            result(
+                BatchStatus::Succeeded,
+                Some(dir.path().join("bo.json").to_string_lossy().into_owned()),
+            ),
+        );
+        report
+            .results
+            .insert("cy.json".to_string(), result(BatchStatus::Failed, None));
+
+        let path = dir
+            .path()
+            .join("report.json")
+            .to_string_lossy()
+            .into_owned();
+        report.save(&path).unwrap();
+        let report = BatchReport::load(&path).unwrap();
+        assert!(report.is_done("ayla.json"));
+        // deleted since, generated again
+        assert!(!report.is_done("bo.json"));
+        assert!(!report.is_done("cy.json"));
+        assert!(!report.is_done("dee.json"));
+        assert_eq!(report.count(BatchStatus::Succeeded), 2);
+        assert_eq!(report.count(BatchStatus::Failed), 1);
+    }
+}
@@ -0,0 +1,179 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use fabelis_characterfile::character::Field;
+use fabelis_characterfile::config::CompletionProvider;
+use fabelis_characterfile::consts::{CONFIG_PATH, INPUT_PATH};
+use fabelis_characterfile::version::VersionHistory;
+
+#[derive(Parser)]
+#[command(version, about = "Generate and iterate upon AI character files")]
+pub struct Cli {
+    /// Configuration file
+    #[arg(long, global = true, default_value = CONFIG_PATH)]
+    pub config: String,
+
+    /// Input file with the character name, facts and documents
+    #[arg(long, global = true, default_value = INPUT_PATH)]
+    pub input: String,
+
+    /// Directory everything is written to, overrides `output_dir`
+    #[arg(long, global = true)]
+    pub out_dir: Option<String>,
+
+    /// Character file to work on, overrides `output_file_name`
+    #[arg(long, short, global = true)]
+    pub name: Option<String>,
+
+    /// Branch of the character to work on
+    #[arg(long, short, global = true, default_value = VersionHistory::MAIN)]
+    pub branch: String,
+
+    /// Completion provider, overrides `completion_provider`
+    #[arg(long, global = true)]
+    pub provider: Option<CompletionProvider>,
+
+    /// Completion model, overrides `model` and `<PROVIDER>_COMPLETION_MODEL`
+    #[arg(long, global = true)]
+    pub model: Option<String>,
+
+    /// Review each proposed character before it is saved, enables `review`
+    #[arg(long, global = true)]
+    pub review: bool,
+
+    /// Iterate with patches instead of whole characters, sets `iteration` to `patch`
+    #[arg(long, global = true)]
+    pub patch: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Clone)]
+pub enum Command {
+    /// Generate a new character, replacing any existing one
+    New {
+        #[arg(required = true, num_args = 1..)]
+        instruction: Vec<String>,
+    },
+    /// Iterate once upon the existing character
+    Iterate {
+        #[arg(required = true, num_args = 1..)]
+        instruction: Vec<String>,
+    },
+    /// Interactive session creating or iterating upon the character (default)
+    Chat,
+    /// Apply instructions in order without a terminal, then exit
+    Run {
+        /// Instruction to apply, may be repeated
+        #[arg(long = "message", short)]
+        messages: Vec<String>,
+        /// File with one instruction per line (`-` for stdin), blank lines and `#` comments are
+        /// skipped. Read from stdin when no instruction is given and stdin is not a terminal
+        #[arg(long, short)]
+        file: Option<String>,
+        /// Start from a new character even if one is saved
+        #[arg(long)]
+        fresh: bool,
+    },
+    /// Generate every character of a manifest, resuming where a previous run stopped
+    Batch {
+        /// JSON array or CSV file of entries with name, facts, files, output and instruction
+        manifest: String,
+        /// Generations running at the same time
+        #[arg(long, short, default_value_t = 4)]
+        jobs: usize,
+        /// Progress report, defaults to `<out-dir>/batch/<manifest>.report.json`
+        #[arg(long)]
+        report: Option<String>,
+        /// Ignore the existing report and generate every entry again
+        #[arg(long)]
+        restart: bool,
+    },
+    /// Print or write the character in another format
+    Export {
+        /// Character name or path, defaults to the current character
+        character: Option<String>,
+        #[arg(long, short, value_enum, default_value_t = ExportFormat::Json)]
+        format: ExportFormat,
+        /// Write to a file instead of stdout
+        #[arg(long, short)]
+        output: Option<String>,
+    },
+    /// Check a character file against the schema
+    Validate {
+        /// Character name or path, defaults to the current character
+        character: Option<String>,
+    },
+    /// Compare two character files field by field
+    Diff { old: String, new: String },
+    /// List saved characters
+    List,
+    /// Browse the saved versions of the character
+    History {
+        /// Print the character saved as this version
+        #[arg(long)]
+        show: Option<usize>,
+    },
+    /// Explore alternative directions of the character on separate branches
+    Branch {
+        #[command(subcommand)]
+        command: BranchCommand,
+    },
+    /// Manage the conversations resumed by `chat`, `iterate` and `run`
+    Session {
+        #[command(subcommand)]
+        command: SessionCommand,
+    },
+    /// Keep a field, or one entry of a list field, unchanged by iterations. Lists the locks
+    /// without arguments
+    Lock {
+        field: Option<Field>,
+        /// Entry to pin, starting at 1
+        entry: Option<usize>,
+    },
+    /// Release a locked field or pinned entry
+    Unlock {
+        field: Field,
+        /// Entry to unpin, starting at 1
+        entry: Option<usize>,
+    },
+}
+
+#[derive(Subcommand, Clone)]
+pub enum BranchCommand {
+    /// List branches and the version each one is at
+    List,
+    /// Start a branch from a version, continue on it with `--branch <name>`
+    Fork {
+        // distinct id, `name` is taken by the global `--name`
+        #[arg(id = "branch_name", value_name = "NAME")]
+        name: String,
+        /// Version to fork at, defaults to the head of `--branch`
+        #[arg(long)]
+        at: Option<usize>,
+    },
+    /// Save the head of a branch as the main output file
+    Promote {
+        #[arg(id = "branch_name", value_name = "NAME")]
+        name: String,
+    },
+}
+
+#[derive(Subcommand, Clone)]
+pub enum SessionCommand {
+    /// List saved sessions
+    List,
+    /// Copy a session and its character to a new name
+    Fork { from: String, to: String },
+    /// Forget a session's conversation, keeping the character
+    Clear {
+        /// Session name, defaults to the current character
+        #[arg(id = "session_name", value_name = "NAME")]
+        name: Option<String>,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+pub enum ExportFormat {
+    Json,
+    Markdown,
+}
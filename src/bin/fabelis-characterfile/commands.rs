@@ -0,0 +1,266 @@
+use crate::cli::ExportFormat;
+use fabelis_characterfile::diff::CharacterDiff;
+use fabelis_characterfile::lock::Locks;
+use fabelis_characterfile::session::Session;
+use fabelis_characterfile::version::VersionHistory;
+use fabelis_characterfile::{Character, Config};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+/// Resolves a character argument: existing paths are used as is, bare names are looked up in the
+/// characters directory.
+pub fn character_path(config: &Config, character: &str) -> String {
+    if Path::new(character).exists() || character.contains('/') {
+        return character.to_string();
+    }
+    format!("{}/{}", config.characters_dir(), file_name(character))
+}
+
+/// Appends the `.json` extension when missing.
+pub fn file_name(name: &str) -> String {
+    match name.ends_with(".json") {
+        true => name.to_string(),
+        false => format!("{}.json", name),
+    }
+}
+
+pub fn load(path: &str) -> Result<Character, anyhow::Error> {
+    let mut character = Character::new(path.to_string());
+    character
+        .load()
+        .map_err(|e| anyhow::anyhow!("Failed to load character from {}: {}", path, e))?;
+    Ok(character)
+}
+
+pub fn export(path: &str, format: ExportFormat, output: Option<&str>) -> Result<(), anyhow::Error> {
+    let character = load(path)?;
+    let content = match format {
+        ExportFormat::Json => serde_json::to_string_pretty(&character)? + "\n",
+        ExportFormat::Markdown => character.to_markdown(),
+    };
+    match output {
+        Some(output) => fs::write(output, content)?,
+        None => print!("{}", content),
+    }
+    Ok(())
+}
+
+pub fn validate(path: &str) -> Result<(), anyhow::Error> {
+    let content = fs::read_to_string(path)?;
+    let value: Value = serde_json::from_str(&content)
+        .map_err(|e| anyhow::anyhow!("{} is not valid JSON: {}", path, e))?;
+    Character::from_value(value)?;
+    println!("{} is a valid character", path);
+    Ok(())
+}
+
+pub fn diff(old_path: &str, new_path: &str) -> Result<(), anyhow::Error> {
+    let old = load(old_path)?;
+    let new = load(new_path)?;
+    println!("{}", CharacterDiff::new(&old, &new).render());
+    Ok(())
+}
+
+pub fn list(config: &Config) -> Result<(), anyhow::Error> {
+    let dir = config.characters_dir();
+    let mut paths: Vec<_> = match fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect(),
+        Err(_) => vec![],
+    };
+    if paths.is_empty() {
+        println!("No characters in {}", dir);
+        return Ok(());
+    }
+    paths.sort();
+
+    for path in paths {
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let marker = match file_name == config.output_file_name {
+            true => "*",
+            false => " ",
+        };
+        match load(&path.to_string_lossy()) {
+            Ok(character) => println!("{} {}\t{}", marker, file_name, character.alias),
+            Err(_) => println!("{} {}\t(invalid)", marker, file_name),
+        }
+    }
+    Ok(())
+}
+
+pub fn session_list(config: &Config) -> Result<(), anyhow::Error> {
+    let sessions = Session::list(&config.sessions_dir())?;
+    if sessions.is_empty() {
+        println!("No sessions in {}", config.sessions_dir());
+        return Ok(());
+    }
+
+    let current = Session::name_for(&config.output_file_name);
+    for session in sessions {
+        let marker = match session.name == current {
+            true => "*",
+            false => " ",
+        };
+        println!(
+            "{} {}\t{} messages\tupdated {}",
+            marker,
+            session.name,
+            session.history.len(),
+            timestamp(&session.updated_at)
+        );
+    }
+    Ok(())
+}
+
+pub fn session_fork(config: &Config, from: &str, to: &str) -> Result<(), anyhow::Error> {
+    let (from, to) = (Session::name_for(from), Session::name_for(to));
+    let session = Session::fork(&config.sessions_dir(), &from, &to)?;
+
+    // the fork iterates upon its own copy of the character
+    let from_character = format!("{}/{}", config.characters_dir(), file_name(&from));
+    let to_character = format!("{}/{}", config.characters_dir(), file_name(&to));
+    if Path::new(&from_character).exists() && !Path::new(&to_character).exists() {
+        fs::copy(&from_character, &to_character)?;
+    }
+    println!(
+        "Forked session {} into {} ({} messages), continue with `--name {}`",
+        from,
+        session.name,
+        session.history.len(),
+        session.name
+    );
+    Ok(())
+}
+
+pub fn session_clear(config: &Config, name: Option<&str>) -> Result<(), anyhow::Error> {
+    let name = Session::name_for(name.unwrap_or(&config.output_file_name));
+    Session::clear(&config.sessions_dir(), &name)?;
+    println!("Cleared session {}", name);
+    Ok(())
+}
+
+pub fn history(config: &Config, branch: &str) -> Result<(), anyhow::Error> {
+    let versions = VersionHistory::load(&config.versions_dir(), &config.output_file_name)?;
+    if versions.versions.is_empty() {
+        println!("No versions of {}", config.output_file_name);
+        return Ok(());
+    }
+
+    for version in versions.versions.iter() {
+        let branches = versions.branches_at(version.id);
+        let marker = match branches.contains(&branch) {
+            true => "*",
+            false => " ",
+        };
+        let parent = version
+            .parent
+            .map(|parent| parent.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let model = match version.provider.is_empty() {
+            true => "-".to_string(),
+            false => format!("{}/{}", version.provider, version.model),
+        };
+        println!(
+            "{} {:>3}  parent {:>3}  {}  {}  {}",
+            marker,
+            version.id,
+            parent,
+            timestamp(&version.timestamp),
+            model,
+            version.instruction
+        );
+        if !branches.is_empty() {
+            println!("        ({})", branches.join(", "));
+        }
+    }
+    Ok(())
+}
+
+pub fn history_show(config: &Config, id: usize) -> Result<(), anyhow::Error> {
+    let versions = VersionHistory::load(&config.versions_dir(), &config.output_file_name)?;
+    let version = versions
+        .get(id)
+        .ok_or_else(|| anyhow::anyhow!("Version {} not found", id))?;
+    println!("{}", serde_json::to_string_pretty(&version.character)?);
+    Ok(())
+}
+
+/// Shortens an RFC 3339 timestamp for listings.
+fn timestamp(rfc3339: &str) -> String {
+    match chrono::DateTime::parse_from_rfc3339(rfc3339) {
+        Ok(time) => time.format("%Y-%m-%d %H:%M:%S").to_string(),
+        Err(_) => rfc3339.to_string(),
+    }
+}
+
+pub fn branch_list(config: &Config) -> Result<(), anyhow::Error> {
+    let versions = VersionHistory::load(&config.versions_dir(), &config.output_file_name)?;
+    if versions.branches.is_empty() {
+        println!("No branches of {}", config.output_file_name);
+        return Ok(());
+    }
+
+    for (name, branch) in versions.branches.iter() {
+        let forked = branch
+            .forked_from
+            .map(|id| format!(", forked at version {}", id))
+            .unwrap_or_default();
+        println!(
+            "{}\tversion {}{}\t{}",
+            name,
+            branch.head,
+            forked,
+            config.character_path(name)
+        );
+    }
+    Ok(())
+}
+
+pub fn branch_fork(
+    config: &Config,
+    from: &str,
+    name: &str,
+    at: Option<usize>,
+) -> Result<(), anyhow::Error> {
+    let mut versions = VersionHistory::load(&config.versions_dir(), &config.output_file_name)?;
+    let version = versions.fork_to(from, name, at, config.character_path(name))?;
+    println!(
+        "Forked branch {} at version {} to {}, continue with `--branch {}`",
+        name, version.id, version.character.path, name
+    );
+    Ok(())
+}
+
+pub fn branch_promote(config: &Config, name: &str) -> Result<(), anyhow::Error> {
+    let mut versions = VersionHistory::load(&config.versions_dir(), &config.output_file_name)?;
+    let version = versions.promote_to(name, config.character_path(VersionHistory::MAIN))?;
+    println!(
+        "Promoted branch {} (version {}) to {}",
+        name, version.id, version.character.path
+    );
+    Ok(())
+}
+
+pub fn locks(config: &Config) -> Result<(), anyhow::Error> {
+    let locks = Locks::effective(config)?;
+    if locks.is_empty() {
+        println!("No locks on {}", config.output_file_name);
+        return Ok(());
+    }
+
+    for field in locks.fields.iter() {
+        match config.locked_fields.contains(field) {
+            true => println!("{}\tlocked by `locked_fields`", field),
+            false => println!("{}\tlocked", field),
+        }
+    }
+    for (field, entries) in locks.entries.iter() {
+        for entry in entries {
+            println!("{}\tpinned \"{}\"", field, entry);
+        }
+    }
+    Ok(())
+}
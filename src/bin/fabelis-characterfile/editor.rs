@@ -0,0 +1,145 @@
+use crate::slash::COMMANDS;
+use fabelis_characterfile::character::Field;
+use log::warn;
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use std::fs;
+use std::path::Path;
+
+/// Line starting and ending a block of pasted text.
+const PASTE_DELIMITER: &str = "\"\"\"";
+
+/// Completes slash commands and the field names they take.
+struct CommandCompleter;
+
+impl Completer for CommandCompleter {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let line = &line[..pos];
+        let start = line.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let word = &line[start..];
+        let previous: Vec<&str> = line[..start].split_whitespace().collect();
+
+        let candidates: Vec<&str> = match previous.as_slice() {
+            [] if word.starts_with('/') => COMMANDS.iter().map(|command| command.name).collect(),
+            [name]
+                if COMMANDS.iter().any(|command| {
+                    command.name == *name && command.args.starts_with("<field>")
+                }) =>
+            {
+                Field::ALL.iter().map(|field| field.name()).collect()
+            }
+            _ => vec![],
+        };
+        let pairs = candidates
+            .into_iter()
+            .filter(|candidate| candidate.starts_with(word))
+            .map(|candidate| Pair {
+                display: candidate.to_string(),
+                replacement: format!("{} ", candidate),
+            })
+            .collect();
+        Ok((start, pairs))
+    }
+}
+
+impl Hinter for CommandCompleter {
+    type Hint = String;
+}
+
+impl Highlighter for CommandCompleter {}
+
+impl Validator for CommandCompleter {}
+
+impl Helper for CommandCompleter {}
+
+/// Line editor of the interactive session, keeping what was typed in a history file.
+pub struct LineEditor {
+    editor: Editor<CommandCompleter, DefaultHistory>,
+    history_path: String,
+}
+
+impl LineEditor {
+    /// An editor resuming the history saved at `history_path`.
+    pub fn new(history_path: String) -> Result<Self, anyhow::Error> {
+        let mut editor = Editor::new()?;
+        editor.set_helper(Some(CommandCompleter));
+        if Path::new(&history_path).exists() {
+            if let Err(e) = editor.load_history(&history_path) {
+                warn!("[CHARGEN] Failed to load input history: {}", e);
+            }
+        }
+        Ok(LineEditor {
+            editor,
+            history_path,
+        })
+    }
+
+    /// Reads one entry, `None` at end of input. A line ending with `\` continues on the next one,
+    /// and everything between two `"""` lines is read as a single entry. Ctrl-C discards the
+    /// entry being typed.
+    pub fn read(&mut self, prompt: &str) -> Result<Option<String>, ReadlineError> {
+        let mut lines = vec![];
+        let mut pasting = false;
+        loop {
+            let prompt = match lines.is_empty() && !pasting {
+                true => prompt,
+                false => "... ",
+            };
+            let line = match self.editor.readline(prompt) {
+                Ok(line) => line,
+                Err(ReadlineError::Eof) if lines.is_empty() => return Ok(None),
+                Err(ReadlineError::Eof) => break,
+                Err(ReadlineError::Interrupted) => return Ok(Some(String::new())),
+                Err(e) => return Err(e),
+            };
+
+            if line.trim() == PASTE_DELIMITER {
+                match pasting {
+                    true => break,
+                    false => pasting = true,
+                }
+                continue;
+            }
+            if pasting {
+                lines.push(line);
+                continue;
+            }
+            match line.strip_suffix('\\') {
+                Some(line) => lines.push(line.to_string()),
+                None => {
+                    lines.push(line);
+                    break;
+                }
+            }
+        }
+
+        let entry = lines.join("\n");
+        if !entry.trim().is_empty() {
+            self.editor.add_history_entry(entry.as_str())?;
+            if let Err(e) = self.save_history() {
+                warn!("[CHARGEN] Failed to save input history: {}", e);
+            }
+        }
+        Ok(Some(entry))
+    }
+
+    fn save_history(&mut self) -> Result<(), anyhow::Error> {
+        if let Some(parent) = Path::new(&self.history_path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+        self.editor.save_history(&self.history_path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,297 @@
+mod cli;
+mod commands;
+mod editor;
+mod repl;
+mod review;
+mod run;
+mod slash;
+
+use clap::Parser;
+use cli::{BranchCommand, Cli, Command, SessionCommand};
+use dotenv::dotenv;
+use fabelis_characterfile::batch::{Batch, BatchStatus};
+use fabelis_characterfile::character::ValidationError;
+use fabelis_characterfile::completion::{ProviderModel, ProviderRegistry};
+use fabelis_characterfile::config::IterationMode;
+use fabelis_characterfile::lock::Locks;
+use fabelis_characterfile::patch::PatchError;
+use fabelis_characterfile::repair::RepairError;
+use fabelis_characterfile::version::VersionHistory;
+use fabelis_characterfile::{CharacterfileError, Config, Generator, Input};
+use fern::colors::{Color, ColoredLevelConfig};
+use log::{info, warn};
+use std::path::Path;
+use std::process::ExitCode;
+
+// exit statuses for scripted runs, clap exits with 2 on invalid usage
+const EXIT_PROVIDER: u8 = 3;
+const EXIT_INVALID_CHARACTER: u8 = 4;
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli).await {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            exit_code(&e)
+        }
+    }
+}
+
+/// Provider failures and unusable characters get their own status so scripts can tell them
+/// apart from configuration mistakes.
+fn exit_code(error: &anyhow::Error) -> ExitCode {
+    if error.is::<CharacterfileError>() {
+        ExitCode::from(EXIT_PROVIDER)
+    } else if error.is::<ValidationError>() || error.is::<RepairError>() || error.is::<PatchError>()
+    {
+        ExitCode::from(EXIT_INVALID_CHARACTER)
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), anyhow::Error> {
+    let command = cli.command.clone().unwrap_or(Command::Chat);
+
+    // init logging, kept on stderr so exports and listings can be piped
+    let colors = ColoredLevelConfig::new()
+        .error(Color::Red)
+        .warn(Color::Yellow)
+        .info(Color::Green)
+        .debug(Color::Blue)
+        .trace(Color::Magenta);
+
+    let level = match command {
+        Command::New { .. }
+        | Command::Iterate { .. }
+        | Command::Chat
+        | Command::Run { .. }
+        | Command::Batch { .. } => log::LevelFilter::Debug,
+        _ => log::LevelFilter::Warn,
+    };
+    fern::Dispatch::new()
+        .format(move |out, message, record| {
+            out.finish(format_args!(
+                "{}[{}] {}",
+                chrono::Local::now().format("[%Y-%m-%d %H:%M:%S]"),
+                colors.color(record.level()),
+                message
+            ))
+        })
+        .level(level)
+        // the line editor logs every keystroke at debug level
+        .level_for("rustyline", log::LevelFilter::Warn)
+        .chain(std::io::stderr())
+        .apply()?;
+    info!("Starting FABELIS.AI Character Gen...");
+
+    match command {
+        Command::New { instruction } => {
+            let mut gen = generator(&cli)?;
+            if let Some(character) =
+                review::generate(&mut gen, &instruction.join(" "), true).await?
+            {
+                info!("[CHARGEN] Character saved to {}", character.path);
+            }
+        }
+        Command::Iterate { instruction } => {
+            let mut gen = generator(&cli)?;
+            let previous = gen.load_existing_character().await.ok();
+            let character = review::generate(&mut gen, &instruction.join(" "), false).await?;
+            repl::saved(&gen, previous.as_ref(), character);
+        }
+        Command::Chat => {
+            let mut gen = generator(&cli)?;
+            let context = repl::Context {
+                input_path: cli.input.clone(),
+                completion_model,
+            };
+            repl::start(&mut gen, context).await;
+        }
+        Command::Run {
+            messages,
+            file,
+            fresh,
+        } => {
+            let instructions = run::instructions(&messages, file.as_deref())?;
+            let mut gen = generator(&cli)?;
+            run::run(&mut gen, &instructions, fresh).await?;
+        }
+        Command::Batch {
+            manifest,
+            jobs,
+            report,
+            restart,
+        } => {
+            let config = load_config(&cli)?;
+            let entries = Batch::load_manifest(&manifest)?;
+            let report_path = report.unwrap_or_else(|| {
+                let stem = Path::new(&manifest)
+                    .file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy();
+                format!("{}/batch/{}.report.json", config.output_dir, stem)
+            });
+            if restart {
+                let _ = std::fs::remove_file(&report_path);
+            }
+            let batch = Batch {
+                entries,
+                concurrency: jobs,
+                report_path,
+            };
+
+            let report = batch.run(&config, completion_model(&config)?).await;
+            let failed = report.count(BatchStatus::Failed);
+            info!(
+                "[BATCH] {} succeeded, {} failed, report saved to {}",
+                report.count(BatchStatus::Succeeded),
+                failed,
+                batch.report_path
+            );
+            if failed > 0 {
+                return Err(anyhow::anyhow!(
+                    "{} character(s) failed, rerun the batch to retry them (see {})",
+                    failed,
+                    batch.report_path
+                ));
+            }
+        }
+        Command::Export {
+            character,
+            format,
+            output,
+        } => {
+            let config = load_config(&cli)?;
+            let path = commands::character_path(
+                &config,
+                character.as_deref().unwrap_or(&config.output_file_name),
+            );
+            commands::export(&path, format, output.as_deref())?;
+        }
+        Command::Validate { character } => {
+            let config = load_config(&cli)?;
+            let path = commands::character_path(
+                &config,
+                character.as_deref().unwrap_or(&config.output_file_name),
+            );
+            commands::validate(&path)?;
+        }
+        Command::Diff { old, new } => {
+            let config = load_config(&cli)?;
+            commands::diff(
+                &commands::character_path(&config, &old),
+                &commands::character_path(&config, &new),
+            )?;
+        }
+        Command::List => commands::list(&load_config(&cli)?)?,
+        Command::History { show } => {
+            let config = load_config(&cli)?;
+            match show {
+                Some(id) => commands::history_show(&config, id)?,
+                None => commands::history(&config, &cli.branch)?,
+            }
+        }
+        Command::Branch { command } => {
+            let config = load_config(&cli)?;
+            match command {
+                BranchCommand::List => commands::branch_list(&config)?,
+                BranchCommand::Fork { name, at } => {
+                    commands::branch_fork(&config, &cli.branch, &name, at)?
+                }
+                BranchCommand::Promote { name } => commands::branch_promote(&config, &name)?,
+            }
+        }
+        Command::Lock { field, entry } => {
+            let config = load_config(&cli)?;
+            match field {
+                Some(field) => println!(
+                    "{}",
+                    Locks::add(&config, &config.character_path(&cli.branch), field, entry)?
+                ),
+                None => commands::locks(&config)?,
+            }
+        }
+        Command::Unlock { field, entry } => {
+            let config = load_config(&cli)?;
+            println!(
+                "{}",
+                Locks::remove(&config, &config.character_path(&cli.branch), field, entry)?
+            );
+        }
+        Command::Session { command } => {
+            let config = load_config(&cli)?;
+            match command {
+                SessionCommand::List => commands::session_list(&config)?,
+                SessionCommand::Fork { from, to } => commands::session_fork(&config, &from, &to)?,
+                SessionCommand::Clear { name } => {
+                    commands::session_clear(&config, name.as_deref())?
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Loads the configuration file with the command line overrides applied.
+fn load_config(cli: &Cli) -> Result<Config, anyhow::Error> {
+    info!("[SETUP] Loading from {}...", cli.config);
+    let mut config = Config::from_path(&cli.config)
+        .map_err(|e| anyhow::anyhow!("Failed to load {}: {}", cli.config, e))?;
+    if let Some(out_dir) = &cli.out_dir {
+        config.output_dir = out_dir.clone();
+    }
+    if let Some(name) = &cli.name {
+        config.output_file_name = commands::file_name(name);
+    }
+    if let Some(provider) = &cli.provider {
+        config.completion_provider = provider.clone();
+    }
+    if let Some(model) = &cli.model {
+        config.model = Some(model.clone());
+    }
+    if cli.review {
+        config.review = true;
+    }
+    if cli.patch {
+        config.iteration = IterationMode::Patch;
+    }
+    info!("[SETUP] Loaded {}: {:#?}", cli.config, config);
+    Ok(config)
+}
+
+fn generator(cli: &Cli) -> Result<Generator<ProviderModel>, anyhow::Error> {
+    let config = load_config(cli)?;
+
+    // load input.json
+    info!("[SETUP] Loading from {}...", cli.input);
+    let input = Input::from_path(&cli.input)
+        .map_err(|e| anyhow::anyhow!("Failed to load {}: {}", cli.input, e))?;
+    info!("[SETUP] Loaded {}: {:#?}", cli.input, input);
+
+    let completion_model = completion_model(&config)?;
+    let mut gen = Generator::new(config, input, completion_model);
+    if cli.branch != VersionHistory::MAIN {
+        gen.switch_branch(&cli.branch)?;
+        info!("[SETUP] Switched to branch {}", cli.branch);
+    }
+    Ok(gen)
+}
+
+fn completion_model(config: &Config) -> Result<ProviderModel, anyhow::Error> {
+    std::fs::create_dir_all("in").expect("Failed to input directory");
+
+    // load .env (optional, the mock provider needs no credentials)
+    match dotenv() {
+        Ok(_) => info!("[SETUP] Loaded .env"),
+        Err(e) => warn!("[SETUP] Failed to load .env: {}", e),
+    }
+
+    // load completion model
+    ProviderRegistry::default()
+        .build(config)
+        .map_err(|e| anyhow::anyhow!("Failed to load completion model: {}", e))
+}
@@ -0,0 +1,117 @@
+use crate::editor::LineEditor;
+use crate::review;
+use crate::slash::{self, Flow};
+use fabelis_characterfile::completion::{ProviderResponse, StructuredOutput};
+use fabelis_characterfile::diff::CharacterDiff;
+use fabelis_characterfile::session::Session;
+use fabelis_characterfile::{Character, CharacterfileError, Config, Generator};
+use log::{error, info, warn};
+
+/// What the interactive session needs besides the generator.
+pub struct Context<CM> {
+    /// Input file `/facts` and `/docs` write their changes to.
+    pub input_path: String,
+    /// Builds the completion model `/provider` switches to.
+    pub completion_model: fn(&Config) -> Result<CM, anyhow::Error>,
+}
+
+/// Interactive loop creating or iterating upon the character until the user types `exit`.
+pub async fn start<CM>(gen: &mut Generator<CM>, context: Context<CM>)
+where
+    CM: rig::completion::CompletionModel<Response = ProviderResponse> + StructuredOutput,
+{
+    let config = gen.config();
+    let history_path = format!(
+        "{}/{}.txt",
+        config.input_history_dir(),
+        Session::name_for(&config.output_file_name)
+    );
+    let mut editor = match LineEditor::new(history_path) {
+        Ok(editor) => editor,
+        Err(e) => {
+            error!("[CHARGEN] Failed to start the line editor: {}", e);
+            return;
+        }
+    };
+    info!("[CHARGEN] Started (type '/help' for commands, 'exit' to quit)");
+
+    loop {
+        let existing = gen.load_existing_character().await;
+        match existing {
+            Ok(_) => info!("[CHARGEN] Loaded existing character from output destination. Enter a prompt to iterate upon the character. (type 'exit' to quit)"),
+            Err(ref e) => {
+                warn!(
+                    "[CHARGEN] Failed to load existing character from output destination: {}",
+                    e
+                );
+                info!("[CHARGEN] Enter a prompt to generate a new character. (type 'exit' to quit)");
+            }
+        }
+
+        let user_input = match editor.read("You: ") {
+            Ok(Some(user_input)) => user_input,
+            Ok(None) => {
+                info!("[CHARGEN] End of input, exiting...");
+                break;
+            }
+            Err(e) => {
+                error!("[CHARGEN] Failed to read input: {}", e);
+                break;
+            }
+        };
+
+        let user_input = user_input.trim();
+
+        // models cant take empty messages
+        if user_input.is_empty() {
+            continue;
+        }
+
+        // check for exit
+        if user_input.eq_ignore_ascii_case("exit") {
+            info!("[CHARGEN] Exiting...");
+            break;
+        }
+
+        match slash::dispatch(gen, &context, user_input).await {
+            Some(Ok(Flow::Continue)) => continue,
+            Some(Ok(Flow::Exit)) => {
+                info!("[CHARGEN] Exiting...");
+                break;
+            }
+            Some(Err(e)) => {
+                failed(e);
+                continue;
+            }
+            None => {}
+        }
+
+        match review::generate(gen, user_input, existing.is_err()).await {
+            Ok(character) => saved(gen, existing.as_ref().ok(), character),
+            Err(e) => failed(e),
+        }
+    }
+}
+
+/// Reports a saved character with its changes, nothing for a rejected proposal.
+pub fn saved<CM>(gen: &Generator<CM>, previous: Option<&Character>, character: Option<Character>)
+where
+    CM: rig::completion::CompletionModel<Response = ProviderResponse> + StructuredOutput,
+{
+    let Some(character) = character else {
+        return;
+    };
+    // reviewed proposals were already shown
+    if let (Some(previous), false) = (previous, gen.config().review) {
+        println!("{}", CharacterDiff::new(previous, &character).render());
+    }
+    info!("[CHARGEN] Character saved to {}", character.path);
+}
+
+// provider failures are recoverable, keep the session and its history alive
+fn failed(e: anyhow::Error) {
+    match e.is::<CharacterfileError>() {
+        true => error!("[CHARGEN][AGENT] {}", e),
+        false => error!("[CHARGEN] {}", e),
+    }
+}
@@ -0,0 +1,140 @@
+use fabelis_characterfile::character::Field;
+use fabelis_characterfile::completion::{ProviderResponse, StructuredOutput};
+use fabelis_characterfile::diff::CharacterDiff;
+use fabelis_characterfile::gen::Proposal;
+use fabelis_characterfile::{Character, Generator};
+use log::{error, info};
+use std::io::{self, Write};
+use std::process;
+
+/// What to do with a proposed character.
+pub enum Decision {
+    Accept,
+    Reject,
+}
+
+/// Shows the proposed changes and asks whether to save them, narrowing or editing the proposal
+/// on request. End of input rejects it.
+pub fn review(proposal: &mut Proposal) -> Decision {
+    loop {
+        match &proposal.previous {
+            Some(previous) => println!(
+                "{}",
+                CharacterDiff::new(previous, &proposal.character).render()
+            ),
+            None => println!("{}", proposal.character.to_markdown()),
+        }
+
+        let Some(answer) = ask("Save? [a]ccept, [r]eject, accept [f]ields, [e]dit: ") else {
+            return Decision::Reject;
+        };
+        match answer.to_lowercase().as_str() {
+            "a" | "accept" | "y" | "yes" => return Decision::Accept,
+            "r" | "reject" | "n" | "no" => return Decision::Reject,
+            "f" | "fields" => {
+                let Some(fields) = ask("Fields to keep (comma separated): ") else {
+                    return Decision::Reject;
+                };
+                let fields = fields
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|field| !field.is_empty())
+                    .map(str::parse::<Field>)
+                    .collect::<Result<Vec<_>, _>>();
+                match fields.and_then(|fields| proposal.keep_fields(&fields)) {
+                    Ok(_) => return Decision::Accept,
+                    Err(e) => error!("[CHARGEN] {}", e),
+                }
+            }
+            "e" | "edit" => match edit(&proposal.character) {
+                Ok(character) => {
+                    if let Err(e) = proposal.replace(character) {
+                        error!("[CHARGEN] {}", e);
+                    }
+                }
+                Err(e) => error!("[CHARGEN] {}", e),
+            },
+            _ => info!("[CHARGEN] Answer a, r, f or e"),
+        }
+    }
+}
+
+/// Prints `prompt` and reads one trimmed line, `None` at end of input.
+fn ask(prompt: &str) -> Option<String> {
+    print!("{}", prompt);
+    io::stdout().flush().unwrap();
+    let mut answer = String::new();
+    match io::stdin().read_line(&mut answer) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(answer.trim().to_string()),
+    }
+}
+
+/// Opens the character in `$VISUAL` or `$EDITOR` (`vi` by default) and validates the result.
+fn edit(character: &Character) -> Result<Character, anyhow::Error> {
+    let path = std::env::temp_dir().join(format!("characterfile-{}.json", process::id()));
+    std::fs::write(&path, serde_json::to_string_pretty(character)?)?;
+
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    // editors are often configured with arguments, e.g. `code --wait`
+    let mut args = editor.split_whitespace();
+    let program = args
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("No editor configured"))?;
+    let status = process::Command::new(program)
+        .args(args)
+        .arg(&path)
+        .status()
+        .map_err(|e| anyhow::anyhow!("Failed to start editor `{}`: {}", editor, e))?;
+    let content = std::fs::read_to_string(&path);
+    let _ = std::fs::remove_file(&path);
+    if !status.success() {
+        return Err(anyhow::anyhow!(
+            "Editor exited with {}, keeping the proposal",
+            status
+        ));
+    }
+
+    let value = serde_json::from_str(&content?)
+        .map_err(|e| anyhow::anyhow!("Edited character is not valid JSON: {}", e))?;
+    Ok(Character::from_value(value)?)
+}
+
+/// Creates (`create`) or iterates upon the character, asking before saving it when `review` is
+/// enabled. `None` when the proposal was rejected.
+pub async fn generate<CM>(
+    gen: &mut Generator<CM>,
+    instruction: &str,
+    create: bool,
+) -> Result<Option<Character>, anyhow::Error>
+where
+    CM: rig::completion::CompletionModel<Response = ProviderResponse> + StructuredOutput,
+{
+    let proposal = match create {
+        true => gen.propose_create(instruction).await?,
+        false => gen.propose_iterate(instruction).await?,
+    };
+    decide(gen, proposal)
+}
+
+/// Saves the proposal, after asking when `review` is enabled. `None` when it was rejected.
+pub fn decide<CM>(
+    gen: &mut Generator<CM>,
+    mut proposal: Proposal,
+) -> Result<Option<Character>, anyhow::Error>
+where
+    CM: rig::completion::CompletionModel<Response = ProviderResponse> + StructuredOutput,
+{
+    if !gen.config().review {
+        return gen.commit(proposal).map(Some);
+    }
+    match review(&mut proposal) {
+        Decision::Accept => gen.commit(proposal).map(Some),
+        Decision::Reject => {
+            info!("[CHARGEN] Proposal rejected, nothing saved");
+            Ok(None)
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use fabelis_characterfile::completion::ProviderModel;
+use fabelis_characterfile::diff::CharacterDiff;
+use fabelis_characterfile::Generator;
+use log::info;
+use std::fs;
+use std::io::{self, IsTerminal, Read};
+
+/// Collects the instructions of a `run` from the flags, the instruction file or stdin.
+pub fn instructions(messages: &[String], file: Option<&str>) -> Result<Vec<String>, anyhow::Error> {
+    let mut instructions = messages.to_vec();
+    let content = match file {
+        Some("-") => Some(read_stdin()?),
+        Some(path) => Some(
+            fs::read_to_string(path)
+                .map_err(|e| anyhow::anyhow!("Failed to read instructions from {}: {}", path, e))?,
+        ),
+        None if instructions.is_empty() && !io::stdin().is_terminal() => Some(read_stdin()?),
+        None => None,
+    };
+    if let Some(content) = content {
+        instructions.extend(
+            content
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(str::to_string),
+        );
+    }
+
+    if instructions.is_empty() {
+        return Err(anyhow::anyhow!(
+            "No instructions given, pass --message, --file or pipe them to stdin"
+        ));
+    }
+    Ok(instructions)
+}
+
+fn read_stdin() -> Result<String, anyhow::Error> {
+    let mut content = String::new();
+    io::stdin().read_to_string(&mut content)?;
+    Ok(content)
+}
+
+/// Applies every instruction in order, creating the character first when none is saved (or
+/// `fresh` is set). Stops at the first failure.
+pub async fn run(
+    gen: &mut Generator<ProviderModel>,
+    instructions: &[String],
+    fresh: bool,
+) -> Result<(), anyhow::Error> {
+    for (i, instruction) in instructions.iter().enumerate() {
+        info!(
+            "[CHARGEN] Instruction {}/{}: {}",
+            i + 1,
+            instructions.len(),
+            instruction
+        );
+        let previous = match fresh && i == 0 {
+            true => None,
+            false => gen.load_existing_character().await.ok(),
+        };
+        let result = match previous.is_none() {
+            true => gen.create(instruction).await,
+            false => gen.iterate(instruction).await,
+        };
+        let character = result.map_err(|e| {
+            e.context(format!(
+                "Instruction {}/{} failed",
+                i + 1,
+                instructions.len()
+            ))
+        })?;
+        if let Some(previous) = previous {
+            info!(
+                "[CHARGEN] Changes:\n{}",
+                CharacterDiff::new(&previous, &character).render()
+            );
+        }
+        info!("[CHARGEN] Character saved to {}", character.path);
+    }
+    println!("{}", gen.character_path());
+    Ok(())
+}
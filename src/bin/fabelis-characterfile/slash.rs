@@ -0,0 +1,613 @@
+use crate::repl::{self, Context};
+use crate::review;
+use fabelis_characterfile::character::Field;
+use fabelis_characterfile::completion::{ProviderResponse, StructuredOutput};
+use fabelis_characterfile::gen::FieldEdit;
+use fabelis_characterfile::{Character, Generator};
+use log::info;
+use std::path::Path;
+
+/// A command of the interactive session, anything else typed is an instruction to the model.
+pub struct SlashCommand {
+    pub name: &'static str,
+    pub args: &'static str,
+    pub help: &'static str,
+}
+
+pub const COMMANDS: &[SlashCommand] = &[
+    SlashCommand {
+        name: "/help",
+        args: "",
+        help: "List the commands",
+    },
+    SlashCommand {
+        name: "/show",
+        args: "[markdown]",
+        help: "Print the character",
+    },
+    SlashCommand {
+        name: "/facts",
+        args: "[add <fact> | remove <n>]",
+        help: "List, add or remove facts of the input",
+    },
+    SlashCommand {
+        name: "/docs",
+        args: "[add <file> | remove <n>]",
+        help: "List, attach or detach documents under in/",
+    },
+    SlashCommand {
+        name: "/provider",
+        args: "[<provider> [model]]",
+        help: "Show or switch the completion provider",
+    },
+    SlashCommand {
+        name: "/save-as",
+        args: "<name>",
+        help: "Save a copy of the character under another name",
+    },
+    SlashCommand {
+        name: "/prompt",
+        args: "[<text> | reset]",
+        help: "Show, replace or reset the system prompt",
+    },
+    SlashCommand {
+        name: "/history",
+        args: "",
+        help: "List the saved versions of the character",
+    },
+    SlashCommand {
+        name: "/clear",
+        args: "",
+        help: "Forget the conversation, keeping the character",
+    },
+    SlashCommand {
+        name: "/undo",
+        args: "",
+        help: "Restore the previous version",
+    },
+    SlashCommand {
+        name: "/redo",
+        args: "",
+        help: "Restore the version last undone",
+    },
+    SlashCommand {
+        name: "/checkout",
+        args: "<version>",
+        help: "Restore any saved version",
+    },
+    SlashCommand {
+        name: "/branches",
+        args: "",
+        help: "List the branches",
+    },
+    SlashCommand {
+        name: "/branch",
+        args: "<name>",
+        help: "Continue on another branch",
+    },
+    SlashCommand {
+        name: "/fork",
+        args: "<name> [version]",
+        help: "Start a branch and continue on it",
+    },
+    SlashCommand {
+        name: "/promote",
+        args: "[branch]",
+        help: "Save a branch as the main output file",
+    },
+    SlashCommand {
+        name: "/locks",
+        args: "",
+        help: "List locked fields and pinned entries",
+    },
+    SlashCommand {
+        name: "/lock",
+        args: "<field> [entry]",
+        help: "Lock a field or pin one of its entries",
+    },
+    SlashCommand {
+        name: "/unlock",
+        args: "<field> [entry]",
+        help: "Release a locked field or pinned entry",
+    },
+    SlashCommand {
+        name: "/regen",
+        args: "<field>",
+        help: "Write a field anew",
+    },
+    SlashCommand {
+        name: "/expand",
+        args: "<field> [count]",
+        help: "Add entries or detail to a field",
+    },
+    SlashCommand {
+        name: "/condense",
+        args: "<field>",
+        help: "Tighten a field",
+    },
+    SlashCommand {
+        name: "/exit",
+        args: "",
+        help: "Quit (also `exit`)",
+    },
+];
+
+/// What the session does after a command.
+pub enum Flow {
+    Continue,
+    Exit,
+}
+
+/// Runs `user_input` when it is a slash command, `None` for instructions to the model.
+pub async fn dispatch<CM>(
+    gen: &mut Generator<CM>,
+    context: &Context<CM>,
+    user_input: &str,
+) -> Option<Result<Flow, anyhow::Error>>
+where
+    CM: rig::completion::CompletionModel<Response = ProviderResponse> + StructuredOutput,
+{
+    let mut args = user_input.split_whitespace();
+    let name = args.next().filter(|name| name.starts_with('/'))?;
+    let args: Vec<&str> = args.collect();
+
+    let result = match name {
+        "/help" => {
+            help();
+            Ok(())
+        }
+        "/exit" | "/quit" => return Some(Ok(Flow::Exit)),
+        "/show" => show(gen, &args),
+        "/facts" => facts(gen, context, &args),
+        "/docs" => docs(gen, context, &args),
+        "/provider" => provider(gen, context, &args),
+        "/save-as" => save_as(gen, &args),
+        "/prompt" => prompt(gen, &args),
+        "/history" => history(gen),
+        "/clear" => gen.clear_session().map(|_| {
+            info!(
+                "[CHARGEN] Cleared the conversation of {}",
+                gen.session().name
+            )
+        }),
+        "/undo" | "/redo" | "/checkout" => version(gen, name, &args),
+        "/branches" | "/branch" | "/fork" | "/promote" => branch(gen, name, &args),
+        "/locks" | "/lock" | "/unlock" => lock(gen, name, &args),
+        "/regen" | "/expand" | "/condense" => field(gen, name, &args).await,
+        _ => Err(anyhow::anyhow!(
+            "Unknown command {}, /help lists them",
+            name
+        )),
+    };
+    Some(result.map(|_| Flow::Continue))
+}
+
+fn usage(name: &str) -> anyhow::Error {
+    let args = COMMANDS
+        .iter()
+        .find(|command| command.name == name)
+        .map(|command| command.args)
+        .unwrap_or_default();
+    let usage = format!("{} {}", name, args);
+    anyhow::anyhow!("Usage: {}", usage.trim_end())
+}
+
+fn help() {
+    for command in COMMANDS {
+        let usage = format!("{} {}", command.name, command.args);
+        println!("{:<36}{}", usage.trim_end(), command.help);
+    }
+    println!("Anything else is sent to the model as an instruction.");
+}
+
+fn show<CM>(gen: &Generator<CM>, args: &[&str]) -> Result<(), anyhow::Error>
+where
+    CM: rig::completion::CompletionModel<Response = ProviderResponse> + StructuredOutput,
+{
+    let mut character = Character::new(gen.character_path());
+    character.load()?;
+    match args {
+        [] | ["json"] => println!("{}", serde_json::to_string_pretty(&character)?),
+        ["markdown"] => println!("{}", character.to_markdown()),
+        _ => return Err(usage("/show")),
+    }
+    Ok(())
+}
+
+fn facts<CM>(
+    gen: &mut Generator<CM>,
+    context: &Context<CM>,
+    args: &[&str],
+) -> Result<(), anyhow::Error>
+where
+    CM: rig::completion::CompletionModel<Response = ProviderResponse> + StructuredOutput,
+{
+    let facts = &mut gen.input_mut().facts;
+    match args {
+        [] => {
+            if facts.is_empty() {
+                info!("[CHARGEN] No facts");
+            }
+            for (i, fact) in facts.iter().enumerate() {
+                info!("[CHARGEN] {}. {}", i + 1, fact);
+            }
+            return Ok(());
+        }
+        ["add", fact @ ..] if !fact.is_empty() => {
+            facts.push(fact.join(" "));
+            info!("[CHARGEN] Added fact {}", facts.len());
+        }
+        ["remove", n] => {
+            let fact = remove(facts, n).ok_or_else(|| usage("/facts"))?;
+            info!("[CHARGEN] Removed fact \"{}\"", fact);
+        }
+        _ => return Err(usage("/facts")),
+    }
+    gen.input().save(&context.input_path)
+}
+
+fn docs<CM>(
+    gen: &mut Generator<CM>,
+    context: &Context<CM>,
+    args: &[&str],
+) -> Result<(), anyhow::Error>
+where
+    CM: rig::completion::CompletionModel<Response = ProviderResponse> + StructuredOutput,
+{
+    let files = &mut gen.input_mut().files;
+    match args {
+        [] => {
+            if files.is_empty() {
+                info!("[CHARGEN] No documents attached");
+            }
+            for (i, file) in files.iter().enumerate() {
+                info!("[CHARGEN] {}. in/{}", i + 1, file);
+            }
+            return Ok(());
+        }
+        ["add", file] => {
+            if !Path::new("in").join(file).is_file() {
+                return Err(anyhow::anyhow!("in/{} not found", file));
+            }
+            files.push(file.to_string());
+            info!("[CHARGEN] Attached in/{}", file);
+        }
+        ["remove", n] => {
+            let file = remove(files, n).ok_or_else(|| usage("/docs"))?;
+            info!("[CHARGEN] Detached in/{}", file);
+        }
+        _ => return Err(usage("/docs")),
+    }
+    gen.input().save(&context.input_path)
+}
+
+/// Removes entry `n` (starting at 1).
+fn remove(entries: &mut Vec<String>, n: &str) -> Option<String> {
+    let index = n.parse::<usize>().ok()?.checked_sub(1)?;
+    (index < entries.len()).then(|| entries.remove(index))
+}
+
+fn provider<CM>(
+    gen: &mut Generator<CM>,
+    context: &Context<CM>,
+    args: &[&str],
+) -> Result<(), anyhow::Error>
+where
+    CM: rig::completion::CompletionModel<Response = ProviderResponse> + StructuredOutput,
+{
+    let mut config = gen.config().clone();
+    match args {
+        [] => {
+            info!(
+                "[CHARGEN] Completing with {} ({})",
+                config.completion_provider.0,
+                config.model.as_deref().unwrap_or("provider default model")
+            );
+            return Ok(());
+        }
+        [provider] => {
+            config.completion_provider = provider.parse()?;
+            config.model = None;
+        }
+        [provider, model] => {
+            config.completion_provider = provider.parse()?;
+            config.model = Some(model.to_string());
+        }
+        _ => return Err(usage("/provider")),
+    }
+
+    let completion_model = (context.completion_model)(&config)?;
+    gen.set_completion_model(completion_model);
+    info!(
+        "[CHARGEN] Switched to {}, the next instruction completes with it",
+        config.completion_provider.0
+    );
+    *gen.config_mut() = config;
+    Ok(())
+}
+
+fn save_as<CM>(gen: &Generator<CM>, args: &[&str]) -> Result<(), anyhow::Error>
+where
+    CM: rig::completion::CompletionModel<Response = ProviderResponse> + StructuredOutput,
+{
+    let [name] = args else {
+        return Err(usage("/save-as"));
+    };
+    let mut character = Character::new(gen.character_path());
+    character.load()?;
+    character.path = format!(
+        "{}/{}",
+        gen.config().characters_dir(),
+        crate::commands::file_name(name)
+    );
+    if Path::new(&character.path).exists() {
+        return Err(anyhow::anyhow!("{} already exists", character.path));
+    }
+    character.save()?;
+    info!(
+        "[CHARGEN] Saved a copy to {}, continue on it with `--name {}`",
+        character.path, name
+    );
+    Ok(())
+}
+
+fn prompt<CM>(gen: &mut Generator<CM>, args: &[&str]) -> Result<(), anyhow::Error>
+where
+    CM: rig::completion::CompletionModel<Response = ProviderResponse> + StructuredOutput,
+{
+    match args {
+        [] => println!("{}", gen.preamble()),
+        ["reset"] => {
+            gen.set_preamble(None);
+            info!("[CHARGEN] Restored the default system prompt");
+        }
+        text => {
+            gen.set_preamble(Some(text.join(" ")));
+            info!("[CHARGEN] Replaced the system prompt for this session");
+        }
+    }
+    Ok(())
+}
+
+fn history<CM>(gen: &Generator<CM>) -> Result<(), anyhow::Error>
+where
+    CM: rig::completion::CompletionModel<Response = ProviderResponse> + StructuredOutput,
+{
+    crate::commands::history(gen.config(), gen.branch())
+}
+
+/// Handles `/undo`, `/redo` and `/checkout <version>`.
+fn version<CM>(gen: &Generator<CM>, name: &str, args: &[&str]) -> Result<(), anyhow::Error>
+where
+    CM: rig::completion::CompletionModel<Response = ProviderResponse> + StructuredOutput,
+{
+    let version = match (name, args) {
+        ("/undo", []) => gen.undo()?,
+        ("/redo", []) => gen.redo()?,
+        ("/checkout", [id]) => gen.checkout(id.parse().map_err(|_| usage(name))?)?,
+        _ => return Err(usage(name)),
+    };
+    info!(
+        "[CHARGEN] Restored version {} ({}) to {}",
+        version.id, version.instruction, version.character.path
+    );
+    Ok(())
+}
+
+/// Handles `/branches`, `/branch <name>`, `/fork <name> [version]` and `/promote [branch]`.
+fn branch<CM>(gen: &mut Generator<CM>, command: &str, args: &[&str]) -> Result<(), anyhow::Error>
+where
+    CM: rig::completion::CompletionModel<Response = ProviderResponse> + StructuredOutput,
+{
+    match (command, args) {
+        ("/branches", []) => {
+            for (name, branch) in gen.versions()?.branches.iter() {
+                let marker = match name == gen.branch() {
+                    true => "*",
+                    false => " ",
+                };
+                info!("[CHARGEN] {} {} (version {})", marker, name, branch.head);
+            }
+        }
+        ("/branch", [name]) => {
+            gen.switch_branch(name)?;
+            info!("[CHARGEN] Switched to branch {}", name);
+        }
+        ("/fork", [name, at @ ..]) if at.len() <= 1 => {
+            let at = match at.first().map(|at| at.parse::<usize>()) {
+                Some(Ok(id)) => Some(id),
+                Some(Err(_)) => return Err(usage(command)),
+                None => None,
+            };
+            let version = gen.fork(name, at)?;
+            info!(
+                "[CHARGEN] Forked branch {} at version {}, now iterating on it",
+                name, version.id
+            );
+        }
+        ("/promote", [] | [_]) => {
+            let name = args.first().copied().unwrap_or(gen.branch()).to_string();
+            let version = gen.promote(&name)?;
+            info!(
+                "[CHARGEN] Promoted branch {} (version {}) to {}",
+                name, version.id, version.character.path
+            );
+        }
+        _ => return Err(usage(command)),
+    }
+    Ok(())
+}
+
+/// Handles `/locks`, `/lock <field> [entry]` and `/unlock <field> [entry]`.
+fn lock<CM>(gen: &Generator<CM>, command: &str, args: &[&str]) -> Result<(), anyhow::Error>
+where
+    CM: rig::completion::CompletionModel<Response = ProviderResponse> + StructuredOutput,
+{
+    if command == "/locks" {
+        return match args {
+            [] => crate::commands::locks(gen.config()),
+            _ => Err(usage(command)),
+        };
+    }
+
+    let (field, entry) = match args {
+        [field] => (field.parse::<Field>()?, None),
+        [field, entry] => (
+            field.parse::<Field>()?,
+            Some(entry.parse::<usize>().map_err(|_| usage(command))?),
+        ),
+        _ => return Err(usage(command)),
+    };
+
+    let done = match command {
+        "/lock" => gen.lock(field, entry)?,
+        _ => gen.unlock(field, entry)?,
+    };
+    info!("[CHARGEN] {}", done);
+    Ok(())
+}
+
+/// Handles `/regen <field>`, `/expand <field> [count]` and `/condense <field>`.
+async fn field<CM>(
+    gen: &mut Generator<CM>,
+    command: &str,
+    args: &[&str],
+) -> Result<(), anyhow::Error>
+where
+    CM: rig::completion::CompletionModel<Response = ProviderResponse> + StructuredOutput,
+{
+    let edit = match (command, args) {
+        ("/regen", [_]) => FieldEdit::Regenerate,
+        ("/condense", [_]) => FieldEdit::Condense,
+        ("/expand", [_]) => FieldEdit::Expand(None),
+        ("/expand", [_, count]) => {
+            FieldEdit::Expand(Some(count.parse().map_err(|_| usage(command))?))
+        }
+        _ => return Err(usage(command)),
+    };
+    let field: Field = args[0].parse()?;
+
+    let previous = gen.load_existing_character().await.ok();
+    let proposal = gen.propose_field(edit, field).await?;
+    let saved = review::decide(gen, proposal)?;
+    repl::saved(gen, previous.as_ref(), saved);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fabelis_characterfile::completion::providers::mock::{MockProvider, MockScript};
+    use fabelis_characterfile::completion::ProviderModel;
+    use fabelis_characterfile::{Config, Input};
+
+    fn completion_model(_config: &Config) -> Result<ProviderModel, anyhow::Error> {
+        Ok(ProviderModel::new(Box::new(MockProvider::new(
+            MockScript::default(),
+        )?)))
+    }
+
+    fn generator(dir: &tempfile::TempDir) -> (Generator<ProviderModel>, Context<ProviderModel>) {
+        let config: Config = serde_json::from_value(serde_json::json!({
+            "completion_provider": "mock",
+            "output_file_name": "ayla.json",
+            "output_dir": dir.path().join("out").to_string_lossy(),
+        }))
+        .unwrap();
+        let input = Input {
+            name: "Ayla".to_string(),
+            facts: vec!["pilot".to_string()],
+            files: vec![],
+        };
+        let context = Context {
+            input_path: dir.path().join("input.json").to_string_lossy().to_string(),
+            completion_model,
+        };
+        let gen = Generator::new(config.clone(), input, completion_model(&config).unwrap());
+        (gen, context)
+    }
+
+    async fn error(
+        gen: &mut Generator<ProviderModel>,
+        context: &Context<ProviderModel>,
+        line: &str,
+    ) -> String {
+        match dispatch(gen, context, line).await {
+            Some(Err(e)) => e.to_string(),
+            Some(Ok(_)) => panic!("{} succeeded", line),
+            None => panic!("{} is not a command", line),
+        }
+    }
+
+    #[test]
+    fn usage_lists_the_arguments_of_the_command() {
+        assert_eq!(
+            usage("/expand").to_string(),
+            "Usage: /expand <field> [count]"
+        );
+        assert_eq!(usage("/undo").to_string(), "Usage: /undo");
+    }
+
+    #[tokio::test]
+    async fn leaves_instructions_to_the_model() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut gen, context) = generator(&dir);
+        assert!(dispatch(&mut gen, &context, "make her older")
+            .await
+            .is_none());
+        assert!(dispatch(&mut gen, &context, "  ").await.is_none());
+        assert!(matches!(
+            dispatch(&mut gen, &context, "/exit").await,
+            Some(Ok(Flow::Exit))
+        ));
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut gen, context) = generator(&dir);
+        assert_eq!(
+            error(&mut gen, &context, "/regenerate bio").await,
+            "Unknown command /regenerate, /help lists them"
+        );
+    }
+
+    #[tokio::test]
+    async fn every_listed_command_is_dispatched() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut gen, context) = generator(&dir);
+        for command in COMMANDS {
+            let line = format!("{} too many arguments here", command.name);
+            if let Some(Err(e)) = dispatch(&mut gen, &context, &line).await {
+                assert!(
+                    !e.to_string().starts_with("Unknown command"),
+                    "{} is not dispatched",
+                    command.name
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn parses_field_and_lock_arguments() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut gen, context) = generator(&dir);
+        for (line, expected) in [
+            ("/regen", "Usage: /regen <field>"),
+            ("/regen bio lore", "Usage: /regen <field>"),
+            ("/expand lore many", "Usage: /expand <field> [count]"),
+            ("/expand lore 0", "Expand by a count of at least 1"),
+            (
+                "/condense alias",
+                "The alias is the name of the input, change it there instead",
+            ),
+            ("/regen mood", "Unknown character field: mood"),
+            ("/lock", "Usage: /lock <field> [entry]"),
+            ("/unlock lore first", "Usage: /unlock <field> [entry]"),
+            ("/locks bio", "Usage: /locks"),
+            ("/checkout latest", "Usage: /checkout <version>"),
+        ] {
+            assert_eq!(error(&mut gen, &context, line).await, expected, "{}", line);
+        }
+    }
+}
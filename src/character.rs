@@ -1,7 +1,12 @@
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fmt;
 use std::fs;
+use std::path::Path;
+use std::str::FromStr;
 
-#[derive(Serialize, Deserialize, Clone)]
+/// A character file as consumed by agent frameworks.
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Character {
     pub alias: String,
     pub bio: String,
@@ -10,11 +15,13 @@ pub struct Character {
     pub styles: Vec<String>,
     pub topics: Vec<String>,
     pub inspirations: Vec<String>,
+    /// Where the character is loaded from and saved to, not part of the file itself.
     #[serde(skip)]
     pub path: String,
 }
 
 impl Character {
+    /// An empty character bound to `path`, see [`Character::load`].
     pub fn new(path: String) -> Self {
         Character {
             alias: "".to_string(),
@@ -28,13 +35,321 @@ impl Character {
         }
     }
 
+    /// Replaces every field with the file at [`Character::path`].
     pub fn load(&mut self) -> Result<(), anyhow::Error> {
         let content = fs::read_to_string(self.path.clone())?;
+        let path = std::mem::take(&mut self.path);
         *self = serde_json::from_str(&content)?;
+        self.path = path;
+        Ok(())
+    }
+
+    /// Writes the character as pretty printed JSON to [`Character::path`], creating its directory.
+    pub fn save(&self) -> Result<(), anyhow::Error> {
+        if let Some(parent) = Path::new(&self.path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(self.path.clone(), serde_json::to_string_pretty(self)?)?;
         Ok(())
     }
 
     pub fn to_json_string(&self) -> Result<String, serde_json::Error> {
         serde_json::to_string(self)
     }
+
+    /// Value of a single string field, `None` for list fields.
+    pub fn text(&self, field: Field) -> Option<&String> {
+        match field {
+            Field::Alias => Some(&self.alias),
+            Field::Bio => Some(&self.bio),
+            _ => None,
+        }
+    }
+
+    /// Entries of a list field, `None` for string fields.
+    pub fn list(&self, field: Field) -> Option<&Vec<String>> {
+        match field {
+            Field::Adjectives => Some(&self.adjectives),
+            Field::Lore => Some(&self.lore),
+            Field::Styles => Some(&self.styles),
+            Field::Topics => Some(&self.topics),
+            Field::Inspirations => Some(&self.inspirations),
+            Field::Alias | Field::Bio => None,
+        }
+    }
+
+    pub fn list_mut(&mut self, field: Field) -> Option<&mut Vec<String>> {
+        match field {
+            Field::Adjectives => Some(&mut self.adjectives),
+            Field::Lore => Some(&mut self.lore),
+            Field::Styles => Some(&mut self.styles),
+            Field::Topics => Some(&mut self.topics),
+            Field::Inspirations => Some(&mut self.inspirations),
+            Field::Alias | Field::Bio => None,
+        }
+    }
+
+    /// Replaces `field` with its value in `other`.
+    pub fn copy_field(&mut self, field: Field, other: &Character) {
+        match field {
+            Field::Alias => self.alias = other.alias.clone(),
+            Field::Bio => self.bio = other.bio.clone(),
+            Field::Adjectives => self.adjectives = other.adjectives.clone(),
+            Field::Lore => self.lore = other.lore.clone(),
+            Field::Styles => self.styles = other.styles.clone(),
+            Field::Topics => self.topics = other.topics.clone(),
+            Field::Inspirations => self.inspirations = other.inspirations.clone(),
+        }
+    }
+
+    /// Renders the character as a Markdown document, one section per list field.
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!("# {}\n\n{}\n", self.alias, self.bio);
+        for field in Field::ALL {
+            let Some(entries) = self.list(field).filter(|entries| !entries.is_empty()) else {
+                continue;
+            };
+            let name = field.name();
+            markdown.push_str(&format!(
+                "\n## {}{}\n\n",
+                name[..1].to_uppercase(),
+                &name[1..]
+            ));
+            for entry in entries {
+                markdown.push_str(&format!("- {}\n", entry));
+            }
+        }
+        markdown
+    }
+
+    /// JSON Schema of the serialized character, used to request provider-native structured output.
+    pub fn json_schema() -> Value {
+        let mut properties = serde_json::Map::new();
+        for field in Field::ALL {
+            let schema = if field.is_list() {
+                json!({ "type": "array", "items": { "type": "string" } })
+            } else {
+                json!({ "type": "string" })
+            };
+            properties.insert(field.name().to_string(), schema);
+        }
+        json!({
+            "type": "object",
+            "properties": properties,
+            "required": Field::ALL.map(|field| field.name()),
+            "additionalProperties": false,
+        })
+    }
+
+    /// Deserializes a character, reporting every field that does not match the schema instead of
+    /// stopping at the first one.
+    pub fn from_value(value: Value) -> Result<Self, ValidationError> {
+        let Some(object) = value.as_object() else {
+            return Err(ValidationError(vec![FieldError {
+                field: "$".to_string(),
+                message: "must be a JSON object".to_string(),
+            }]));
+        };
+
+        let mut errors = vec![];
+        for field in Field::ALL {
+            let message = match object.get(field.name()) {
+                None => "is missing",
+                Some(Value::String(_)) if !field.is_list() => continue,
+                Some(Value::Array(items))
+                    if field.is_list() && items.iter().all(Value::is_string) =>
+                {
+                    continue
+                }
+                Some(_) if field.is_list() => "must be an array of strings",
+                Some(_) => "must be a string",
+            };
+            errors.push(FieldError {
+                field: field.name().to_string(),
+                message: message.to_string(),
+            });
+        }
+        if !errors.is_empty() {
+            return Err(ValidationError(errors));
+        }
+
+        serde_json::from_value(value).map_err(|e| {
+            ValidationError(vec![FieldError {
+                field: "$".to_string(),
+                message: e.to_string(),
+            }])
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Field {
+    Alias,
+    Bio,
+    Adjectives,
+    Lore,
+    Styles,
+    Topics,
+    Inspirations,
+}
+
+impl Field {
+    pub const ALL: [Field; 7] = [
+        Field::Alias,
+        Field::Bio,
+        Field::Adjectives,
+        Field::Lore,
+        Field::Styles,
+        Field::Topics,
+        Field::Inspirations,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Field::Alias => "alias",
+            Field::Bio => "bio",
+            Field::Adjectives => "adjectives",
+            Field::Lore => "lore",
+            Field::Styles => "styles",
+            Field::Topics => "topics",
+            Field::Inspirations => "inspirations",
+        }
+    }
+
+    /// Whether the field holds a list of entries rather than a single string.
+    pub fn is_list(&self) -> bool {
+        !matches!(self, Field::Alias | Field::Bio)
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Field {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Field::ALL
+            .into_iter()
+            .find(|field| field.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| anyhow::anyhow!("Unknown character field: {}", s))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` {}", self.field, self.message)
+    }
+}
+
+/// Every schema violation found while deserializing a [`Character`].
+#[derive(Debug, Clone)]
+pub struct ValidationError(pub Vec<FieldError>);
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let errors: Vec<String> = self.0.iter().map(|e| e.to_string()).collect();
+        write!(f, "Character failed validation: {}", errors.join("; "))
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value() -> Value {
+        json!({
+            "alias": "Ayla",
+            "bio": "A pilot.",
+            "adjectives": ["brave"],
+            "lore": ["flew forty missions"],
+            "styles": [],
+            "topics": ["flight"],
+            "inspirations": []
+        })
+    }
+
+    fn errors(value: Value) -> Vec<String> {
+        let error = Character::from_value(value).unwrap_err();
+        error.0.iter().map(|e| e.to_string()).collect()
+    }
+
+    #[test]
+    fn accepts_a_complete_character() {
+        let character = Character::from_value(value()).unwrap();
+        assert_eq!(character.alias, "Ayla");
+        assert_eq!(character.lore, vec!["flew forty missions"]);
+        assert!(character.path.is_empty());
+    }
+
+    #[test]
+    fn reports_a_missing_field() {
+        let mut value = value();
+        value.as_object_mut().unwrap().remove("lore");
+        assert_eq!(errors(value), vec!["`lore` is missing"]);
+    }
+
+    #[test]
+    fn reports_wrongly_typed_fields() {
+        let mut value = value();
+        value["bio"] = json!(["A pilot."]);
+        value["topics"] = json!("flight");
+        let error = Character::from_value(value).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Character failed validation: `bio` must be a string; `topics` must be an array of strings"
+        );
+    }
+
+    #[test]
+    fn reports_a_list_entry_that_is_not_a_string() {
+        let mut value = value();
+        value["adjectives"] = json!(["brave", 3]);
+        assert_eq!(
+            errors(value),
+            vec!["`adjectives` must be an array of strings"]
+        );
+    }
+
+    #[test]
+    fn rejects_anything_but_an_object() {
+        assert_eq!(errors(json!(["Ayla"])), vec!["`$` must be a JSON object"]);
+    }
+
+    #[test]
+    fn schema_requires_every_field() {
+        let schema = Character::json_schema();
+        assert_eq!(schema["type"], "object");
+        assert_eq!(schema["additionalProperties"], false);
+        assert_eq!(
+            schema["required"],
+            json!([
+                "alias",
+                "bio",
+                "adjectives",
+                "lore",
+                "styles",
+                "topics",
+                "inspirations"
+            ])
+        );
+        assert_eq!(schema["properties"]["bio"], json!({ "type": "string" }));
+        assert_eq!(
+            schema["properties"]["lore"],
+            json!({ "type": "array", "items": { "type": "string" } })
+        );
+        assert_eq!(schema["properties"].as_object().unwrap().len(), 7);
+    }
 }
@@ -1,84 +1,123 @@
-use super::enums::CompletionResponseEnum;
-use rig::completion::{CompletionError, CompletionRequest, CompletionResponse, Message};
-use rig::providers::anthropic::completion::Content as AnthropicCompletionContent;
+use super::provider::ProviderResponse;
+use super::retry::{clone_request, RetryPolicy};
+use crate::error::CharacterfileError;
+use crate::transcript::{Transcript, TranscriptEntry};
+use log::warn;
+use rig::completion::{CompletionRequest, CompletionResponse, Message};
 
+/// Sends completion requests, retrying recoverable failures and recording transcripts.
 #[derive(Clone)]
 pub struct Agent<CM>
 where
-    CM: rig::completion::CompletionModel<Response = CompletionResponseEnum>,
+    CM: rig::completion::CompletionModel<Response = ProviderResponse>,
 {
     pub completion_model: CM,
+    pub retry_policy: RetryPolicy,
+    pub transcript: Option<Transcript>,
 }
 
 impl<CM> Agent<CM>
 where
-    CM: rig::completion::CompletionModel<Response = CompletionResponseEnum>,
+    CM: rig::completion::CompletionModel<Response = ProviderResponse>,
 {
     pub fn new(completion_model: CM) -> Self {
-        Self { completion_model }
+        Self {
+            completion_model,
+            retry_policy: RetryPolicy::default(),
+            transcript: None,
+        }
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
     }
 
-    pub async fn prompt(&self, prompt: &str) -> Result<String, CompletionError> {
+    /// Records every provider call, including retried ones, to `transcript`.
+    pub fn with_transcript(mut self, transcript: Transcript) -> Self {
+        self.transcript = Some(transcript);
+        self
+    }
+
+    /// Sends a single prompt and returns the response text.
+    pub async fn prompt(&self, prompt: &str) -> Result<String, CharacterfileError> {
         let request = self.completion_model.completion_request(prompt).build();
 
-        let response = self.completion_model.completion(request).await.unwrap();
-        let content = self.response_extract_content(response);
-        Ok(content)
+        let response = self.completion(request).await?;
+        self.response_extract_content(&response)
     }
 
+    /// Sends a prompt following `history` and returns the response text.
     pub async fn chat(
         &self,
         prompt: &str,
         history: Vec<Message>,
-    ) -> Result<String, CompletionError> {
+    ) -> Result<String, CharacterfileError> {
         let request = self
             .completion_model
             .completion_request(prompt)
             .messages(history)
             .build();
 
-        let response = self.completion_model.completion(request).await.unwrap();
-        let content = self.response_extract_content(response);
-        Ok(content)
+        let response = self.completion(request).await?;
+        self.response_extract_content(&response)
     }
 
+    /// Sends `request`, retrying per [`Agent::retry_policy`] while the failure is retryable.
     pub async fn completion(
         &self,
         request: CompletionRequest,
-    ) -> Result<CompletionResponse<CompletionResponseEnum>, CompletionError> {
-        Ok(self.completion_model.completion(request).await.unwrap())
+    ) -> Result<CompletionResponse<ProviderResponse>, CharacterfileError> {
+        let mut attempt = 1;
+        loop {
+            let result = self
+                .completion_model
+                .completion(clone_request(&request))
+                .await;
+            if let Some(transcript) = &self.transcript {
+                let entry = match &result {
+                    Ok(response) => match self.response_extract_content(response) {
+                        Ok(text) => TranscriptEntry::new(&request, Ok(&text)),
+                        Err(e) => TranscriptEntry::new(&request, Err(e.to_string())),
+                    },
+                    Err(e) => TranscriptEntry::new(&request, Err(e.to_string())),
+                };
+                if let Err(e) = transcript.record(&entry) {
+                    warn!("[CHARGEN][AGENT] Failed to record transcript: {}", e);
+                }
+            }
+
+            let error = match result {
+                Ok(response) => return Ok(response),
+                Err(e) => CharacterfileError::from(e),
+            };
+            if !error.is_retryable() || attempt >= self.retry_policy.max_attempts {
+                return Err(error);
+            }
+
+            let Some(delay) = self.retry_policy.delay(attempt, &error) else {
+                warn!(
+                    "[CHARGEN][AGENT] Provider asks to wait longer than retry.max_delay_ms ({}ms), not retrying",
+                    self.retry_policy.max_delay_ms
+                );
+                return Err(error);
+            };
+            warn!(
+                "[CHARGEN][AGENT] {} (attempt {}/{}, retrying in {:.1}s)",
+                error,
+                attempt,
+                self.retry_policy.max_attempts,
+                delay.as_secs_f64()
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
     }
 
     pub fn response_extract_content(
         &self,
-        response: CompletionResponse<CompletionResponseEnum>,
-    ) -> String {
-        match response.raw_response {
-            CompletionResponseEnum::Anthropic(response) => match &response.content[0] {
-                AnthropicCompletionContent::String(text) => text.clone(),
-                AnthropicCompletionContent::Text { text, .. } => text.clone(),
-                AnthropicCompletionContent::ToolUse { .. } => "Tool use response".to_string(),
-            },
-            CompletionResponseEnum::Cohere(response) => response.text,
-            CompletionResponseEnum::Gemini(response) => response.candidates[0].content.parts[0]
-                .text
-                .as_ref()
-                .expect("Failed to parse Gemini response")
-                .to_string(),
-            CompletionResponseEnum::OpenAI(response) => response.choices[0]
-                .message
-                .content
-                .as_ref()
-                .expect("Failed to parse OpenAI response")
-                .to_string(),
-            CompletionResponseEnum::Perplexity(response) => {
-                response.choices[0].message.content.clone()
-            }
-            CompletionResponseEnum::XAI(response) => response.choices[0]
-                .message
-                .content
-                .clone()
-                .unwrap_or_default(),
-        }
+        response: &CompletionResponse<ProviderResponse>,
+    ) -> Result<String, CharacterfileError> {
+        response.raw_response.text()
     }
 }
@@ -1,5 +1,10 @@
 pub mod agent;
-pub mod enums;
+pub mod provider;
+pub mod providers;
+pub mod retry;
+pub mod structured;
 
 pub use agent::*;
-pub use enums::*;
+pub use provider::*;
+pub use retry::*;
+pub use structured::*;
@@ -0,0 +1,154 @@
+use super::providers;
+use super::structured::{StructuredOutput, StructuredOutputParams};
+use crate::config::Config;
+use crate::error::CharacterfileError;
+use async_trait::async_trait;
+use log::info;
+use rig::completion::{CompletionError, CompletionRequest, CompletionResponse};
+use serde_json::Value;
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Provider specific response, only interpreted by the provider that produced it.
+pub type RawResponse = Box<dyn Any + Send + Sync>;
+
+/// A self-contained completion provider.
+#[async_trait]
+pub trait Provider: Send + Sync {
+    /// Name selecting the provider in `config.json`.
+    fn name(&self) -> &'static str;
+
+    /// Model the provider completes with.
+    fn model(&self) -> &str;
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<RawResponse>, CompletionError>;
+
+    /// Extracts the response text from a [`RawResponse`] returned by [`Provider::completion`].
+    fn extract_text(&self, response: &RawResponse) -> Result<String, CharacterfileError>;
+
+    /// Tools and parameters constraining the response to `schema`. Providers without a native
+    /// facility rely on the prompt alone.
+    fn structured_output(&self, _schema: &Value) -> StructuredOutputParams {
+        StructuredOutputParams::default()
+    }
+}
+
+/// Builds a provider from the loaded configuration.
+pub type ProviderFactory = fn(&Config) -> Result<Box<dyn Provider>, anyhow::Error>;
+
+/// Maps provider names to their factories.
+pub struct ProviderRegistry {
+    factories: BTreeMap<&'static str, ProviderFactory>,
+}
+
+impl ProviderRegistry {
+    /// An empty registry, see [`ProviderRegistry::default`] for the built-in providers.
+    pub fn new() -> Self {
+        ProviderRegistry {
+            factories: BTreeMap::new(),
+        }
+    }
+
+    pub fn register(&mut self, name: &'static str, factory: ProviderFactory) {
+        self.factories.insert(name, factory);
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.factories.keys().copied().collect()
+    }
+
+    /// Builds the provider selected by `config.completion_provider`.
+    pub fn build(&self, config: &Config) -> Result<ProviderModel, anyhow::Error> {
+        let name = config.completion_provider.0.as_str();
+        let factory = self.factories.get(name).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Unknown completion provider `{}` (available: {})",
+                name,
+                self.names().join(", ")
+            )
+        })?;
+        let model = ProviderModel::new(factory(config)?);
+        info!(
+            "[SETUP] Loaded {} Completion Model ({})",
+            model.name(),
+            model.model()
+        );
+        Ok(model)
+    }
+}
+
+impl Default for ProviderRegistry {
+    fn default() -> Self {
+        let mut registry = Self::new();
+        providers::register_builtin(&mut registry);
+        registry
+    }
+}
+
+/// Response of a [`ProviderModel`], paired with the provider able to read it.
+pub struct ProviderResponse {
+    provider: Arc<dyn Provider>,
+    pub raw: RawResponse,
+}
+
+impl ProviderResponse {
+    pub fn text(&self) -> Result<String, CharacterfileError> {
+        self.provider.extract_text(&self.raw)
+    }
+
+    /// Name of the provider that produced the response.
+    pub fn provider(&self) -> &'static str {
+        self.provider.name()
+    }
+
+    /// Model that produced the response.
+    pub fn model(&self) -> &str {
+        self.provider.model()
+    }
+}
+
+/// Clonable handle dispatching rig completions to a registered [`Provider`].
+#[derive(Clone)]
+pub struct ProviderModel(Arc<dyn Provider>);
+
+impl ProviderModel {
+    pub fn new(provider: Box<dyn Provider>) -> Self {
+        ProviderModel(Arc::from(provider))
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.0.name()
+    }
+
+    pub fn model(&self) -> &str {
+        self.0.model()
+    }
+}
+
+impl rig::completion::CompletionModel for ProviderModel {
+    type Response = ProviderResponse;
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<Self::Response>, CompletionError> {
+        let response = self.0.completion(request).await?;
+        Ok(CompletionResponse {
+            choice: response.choice,
+            raw_response: ProviderResponse {
+                provider: self.0.clone(),
+                raw: response.raw_response,
+            },
+        })
+    }
+}
+
+impl StructuredOutput for ProviderModel {
+    fn structured_output(&self, schema: &Value) -> StructuredOutputParams {
+        self.0.structured_output(schema)
+    }
+}
@@ -0,0 +1,116 @@
+use super::{downcast, empty, env_var, model_name};
+use crate::completion::provider::{Provider, RawResponse};
+use crate::completion::structured::StructuredOutputParams;
+use crate::config::Config;
+use crate::error::CharacterfileError;
+use async_trait::async_trait;
+use rig::completion::{
+    CompletionError, CompletionModel, CompletionRequest, CompletionResponse, ToolDefinition,
+};
+use rig::providers::anthropic::{self, completion::Content};
+use serde_json::{json, Value};
+
+pub const NAME: &str = "anthropic";
+
+/// Name of the tool Anthropic is forced to call with the character as its input.
+pub const CHARACTER_TOOL_NAME: &str = "save_character";
+
+pub struct AnthropicProvider {
+    model: anthropic::completion::CompletionModel,
+}
+
+pub fn build(config: &Config) -> Result<Box<dyn Provider>, anyhow::Error> {
+    let api_key = env_var("ANTHROPIC_API_KEY")?;
+    let model = model_name(config, "ANTHROPIC_COMPLETION_MODEL")?;
+    let mut builder = anthropic::ClientBuilder::new(&api_key);
+    if let Some(base_url) = &config.base_url {
+        builder = builder.base_url(base_url);
+    }
+    let client = builder.build();
+    Ok(Box::new(AnthropicProvider {
+        model: client.completion_model(&model),
+    }))
+}
+
+#[async_trait]
+impl Provider for AnthropicProvider {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn model(&self) -> &str {
+        &self.model.model
+    }
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<RawResponse>, CompletionError> {
+        let response = self.model.completion(request).await?;
+        Ok(CompletionResponse {
+            choice: response.choice,
+            raw_response: Box::new(response.raw_response),
+        })
+    }
+
+    fn extract_text(&self, response: &RawResponse) -> Result<String, CharacterfileError> {
+        let response =
+            downcast::<anthropic::completion::CompletionResponse>(response, "Anthropic")?;
+
+        // a forced character tool call carries the character as its input
+        let tool_input = response.content.iter().find_map(|content| match content {
+            Content::ToolUse { name, input, .. } if name == CHARACTER_TOOL_NAME => {
+                Some(input.to_string())
+            }
+            _ => None,
+        });
+        match (tool_input, response.content.first()) {
+            (Some(input), _) => Ok(input),
+            (None, Some(Content::String(text))) => Ok(text.clone()),
+            (None, Some(Content::Text { text, .. })) => Ok(text.clone()),
+            (None, Some(Content::ToolUse { input, .. })) => Ok(input.to_string()),
+            (None, None) if response.stop_reason.as_deref() == Some("refusal") => Err(
+                CharacterfileError::SafetyBlocked("Anthropic refused the request".into()),
+            ),
+            (None, None) => Err(empty("Anthropic")),
+        }
+    }
+
+    fn structured_output(&self, schema: &Value) -> StructuredOutputParams {
+        StructuredOutputParams {
+            tools: vec![ToolDefinition {
+                name: CHARACTER_TOOL_NAME.to_string(),
+                description: "Save the generated character.".to_string(),
+                parameters: schema.clone(),
+            }],
+            additional_params: Some(json!({
+                "tool_choice": { "type": "tool", "name": CHARACTER_TOOL_NAME }
+            })),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::character::Character;
+
+    #[test]
+    fn forces_the_character_tool() {
+        let provider = AnthropicProvider {
+            model: anthropic::ClientBuilder::new("key")
+                .build()
+                .completion_model("claude"),
+        };
+        let schema = Character::json_schema();
+        let params = provider.structured_output(&schema);
+
+        assert_eq!(params.tools.len(), 1);
+        assert_eq!(params.tools[0].name, CHARACTER_TOOL_NAME);
+        assert_eq!(params.tools[0].parameters, schema);
+        assert_eq!(
+            params.additional_params,
+            Some(json!({ "tool_choice": { "type": "tool", "name": "save_character" } }))
+        );
+    }
+}
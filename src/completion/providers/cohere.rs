@@ -0,0 +1,87 @@
+use super::{downcast, empty, env_var, model_name};
+use crate::completion::provider::{Provider, RawResponse};
+use crate::completion::structured::StructuredOutputParams;
+use crate::config::Config;
+use crate::error::CharacterfileError;
+use async_trait::async_trait;
+use rig::completion::{CompletionError, CompletionModel, CompletionRequest, CompletionResponse};
+use rig::providers::cohere;
+use serde_json::{json, Value};
+
+pub const NAME: &str = "cohere";
+
+pub struct CohereProvider {
+    model: cohere::CompletionModel,
+}
+
+pub fn build(config: &Config) -> Result<Box<dyn Provider>, anyhow::Error> {
+    let api_key = env_var("COHERE_API_KEY")?;
+    let model = model_name(config, "COHERE_COMPLETION_MODEL")?;
+    let client = match &config.base_url {
+        Some(base_url) => cohere::Client::from_url(&api_key, base_url),
+        None => cohere::Client::new(&api_key),
+    };
+    Ok(Box::new(CohereProvider {
+        model: client.completion_model(&model),
+    }))
+}
+
+#[async_trait]
+impl Provider for CohereProvider {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn model(&self) -> &str {
+        &self.model.model
+    }
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<RawResponse>, CompletionError> {
+        let response = self.model.completion(request).await?;
+        Ok(CompletionResponse {
+            choice: response.choice,
+            raw_response: Box::new(response.raw_response),
+        })
+    }
+
+    fn extract_text(&self, response: &RawResponse) -> Result<String, CharacterfileError> {
+        let response = downcast::<cohere::CompletionResponse>(response, "Cohere")?;
+        match response.text.is_empty() {
+            true => Err(empty("Cohere")),
+            false => Ok(response.text.clone()),
+        }
+    }
+
+    fn structured_output(&self, schema: &Value) -> StructuredOutputParams {
+        StructuredOutputParams {
+            tools: vec![],
+            additional_params: Some(json!({
+                "response_format": { "type": "json_object", "schema": schema }
+            })),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::character::Character;
+
+    #[test]
+    fn requests_a_json_object_with_the_schema() {
+        let provider = CohereProvider {
+            model: cohere::Client::new("key").completion_model("command"),
+        };
+        let schema = Character::json_schema();
+        let params = provider.structured_output(&schema);
+
+        assert!(params.tools.is_empty());
+        assert_eq!(
+            params.additional_params,
+            Some(json!({ "response_format": { "type": "json_object", "schema": schema } }))
+        );
+    }
+}
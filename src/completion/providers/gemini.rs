@@ -0,0 +1,129 @@
+use super::{downcast, empty, env_var, model_name};
+use crate::completion::provider::{Provider, RawResponse};
+use crate::completion::structured::StructuredOutputParams;
+use crate::config::Config;
+use crate::error::CharacterfileError;
+use async_trait::async_trait;
+use rig::completion::{CompletionError, CompletionModel, CompletionRequest, CompletionResponse};
+use rig::providers::gemini::{
+    self,
+    completion::gemini_api_types::{FinishReason, GenerateContentResponse},
+};
+use serde_json::{json, Value};
+
+pub const NAME: &str = "gemini";
+
+pub struct GeminiProvider {
+    model: gemini::completion::CompletionModel,
+}
+
+pub fn build(config: &Config) -> Result<Box<dyn Provider>, anyhow::Error> {
+    let api_key = env_var("GEMINI_API_KEY")?;
+    let model = model_name(config, "GEMINI_COMPLETION_MODEL")?;
+    let client = match &config.base_url {
+        Some(base_url) => gemini::Client::from_url(&api_key, base_url),
+        None => gemini::Client::new(&api_key),
+    };
+    Ok(Box::new(GeminiProvider {
+        model: client.completion_model(&model),
+    }))
+}
+
+#[async_trait]
+impl Provider for GeminiProvider {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn model(&self) -> &str {
+        &self.model.model
+    }
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<RawResponse>, CompletionError> {
+        let response = self.model.completion(request).await?;
+        Ok(CompletionResponse {
+            choice: response.choice,
+            raw_response: Box::new(response.raw_response),
+        })
+    }
+
+    fn extract_text(&self, response: &RawResponse) -> Result<String, CharacterfileError> {
+        let response = downcast::<GenerateContentResponse>(response, "Gemini")?;
+        if let Some(reason) = response
+            .prompt_feedback
+            .as_ref()
+            .and_then(|feedback| feedback.block_reason.as_ref())
+        {
+            return Err(CharacterfileError::SafetyBlocked(format!(
+                "Gemini blocked the prompt ({:?})",
+                reason
+            )));
+        }
+        let candidate = response.candidates.first().ok_or_else(|| empty("Gemini"))?;
+        if let Some(
+            reason @ (FinishReason::Safety
+            | FinishReason::Blocklist
+            | FinishReason::ProhibitedContent
+            | FinishReason::Spii),
+        ) = &candidate.finish_reason
+        {
+            return Err(CharacterfileError::SafetyBlocked(format!(
+                "Gemini stopped generating ({:?})",
+                reason
+            )));
+        }
+        candidate
+            .content
+            .parts
+            .iter()
+            .find_map(|part| part.text.clone())
+            .ok_or_else(|| empty("Gemini"))
+    }
+
+    fn structured_output(&self, schema: &Value) -> StructuredOutputParams {
+        // gemini accepts an OpenAPI subset without `additionalProperties`
+        let mut schema = schema.clone();
+        if let Some(object) = schema.as_object_mut() {
+            object.remove("additionalProperties");
+        }
+        StructuredOutputParams {
+            tools: vec![],
+            additional_params: Some(json!({
+                "responseMimeType": "application/json",
+                "responseSchema": schema
+            })),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::character::Character;
+
+    #[test]
+    fn sends_the_schema_without_additional_properties() {
+        let provider = GeminiProvider {
+            model: gemini::Client::new("key").completion_model("gemini"),
+        };
+        let schema = Character::json_schema();
+        let params = provider.structured_output(&schema);
+
+        let mut expected = schema.clone();
+        expected
+            .as_object_mut()
+            .unwrap()
+            .remove("additionalProperties");
+        assert!(params.tools.is_empty());
+        assert_eq!(
+            params.additional_params,
+            Some(json!({ "responseMimeType": "application/json", "responseSchema": expected }))
+        );
+        assert!(params.additional_params.unwrap()["responseSchema"]
+            .get("additionalProperties")
+            .is_none());
+    }
+}
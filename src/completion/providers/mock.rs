@@ -0,0 +1,122 @@
+use super::{downcast, empty};
+use crate::completion::provider::{Provider, RawResponse};
+use crate::config::Config;
+use crate::error::CharacterfileError;
+use async_trait::async_trait;
+use rig::completion::{CompletionError, CompletionRequest, CompletionResponse, ModelChoice};
+use serde::Deserialize;
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use std::{env, fs};
+
+pub const NAME: &str = "mock";
+
+/// Scripted responses for the mock provider.
+///
+/// `rules` are checked first against the prompt; otherwise `responses` are served in order.
+/// A response may be a string or any JSON value, which is served serialized.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct MockScript {
+    #[serde(default)]
+    pub responses: Vec<Value>,
+    #[serde(default)]
+    pub rules: Vec<MockRule>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct MockRule {
+    /// Regex matched against the request prompt.
+    pub pattern: String,
+    pub response: Value,
+}
+
+/// Provider answering from a [`MockScript`] without any network access.
+#[derive(Clone)]
+pub struct MockProvider {
+    rules: Vec<(regex::Regex, String)>,
+    responses: Vec<String>,
+    cursor: Arc<Mutex<usize>>,
+}
+
+impl MockProvider {
+    pub fn new(script: MockScript) -> Result<Self, anyhow::Error> {
+        let mut rules = vec![];
+        for rule in script.rules {
+            rules.push((
+                regex::Regex::new(&rule.pattern)?,
+                response_text(rule.response),
+            ));
+        }
+        Ok(Self {
+            rules,
+            responses: script.responses.into_iter().map(response_text).collect(),
+            cursor: Arc::new(Mutex::new(0)),
+        })
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, anyhow::Error> {
+        let script: MockScript = serde_json::from_str(&fs::read_to_string(path)?)?;
+        Self::new(script)
+    }
+
+    fn respond(&self, prompt: &str) -> Result<String, CompletionError> {
+        if let Some((_, response)) = self.rules.iter().find(|(regex, _)| regex.is_match(prompt)) {
+            return Ok(response.clone());
+        }
+
+        let mut cursor = self.cursor.lock().unwrap();
+        let response = self.responses.get(*cursor).cloned().ok_or_else(|| {
+            CompletionError::ResponseError(format!(
+                "Mock script exhausted after {} responses",
+                self.responses.len()
+            ))
+        })?;
+        *cursor += 1;
+        Ok(response)
+    }
+}
+
+fn response_text(response: Value) -> String {
+    match response {
+        Value::String(text) => text,
+        value => value.to_string(),
+    }
+}
+
+/// Reads the script from `MOCK_SCRIPT_PATH`, defaulting to `mock.json`.
+pub fn build(_config: &Config) -> Result<Box<dyn Provider>, anyhow::Error> {
+    let script_path = env::var("MOCK_SCRIPT_PATH").unwrap_or_else(|_| "mock.json".to_string());
+    let provider = MockProvider::from_file(&script_path)
+        .map_err(|e| anyhow::anyhow!("Failed to load mock script {}: {}", script_path, e))?;
+    Ok(Box::new(provider))
+}
+
+#[async_trait]
+impl Provider for MockProvider {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn model(&self) -> &str {
+        "script"
+    }
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<RawResponse>, CompletionError> {
+        let text = self.respond(&request.prompt)?;
+        Ok(CompletionResponse {
+            choice: ModelChoice::Message(text.clone()),
+            raw_response: Box::new(text),
+        })
+    }
+
+    fn extract_text(&self, response: &RawResponse) -> Result<String, CharacterfileError> {
+        let text = downcast::<String>(response, "Mock")?;
+        match text.is_empty() {
+            true => Err(empty("Mock")),
+            false => Ok(text.clone()),
+        }
+    }
+}
@@ -0,0 +1,93 @@
+#[cfg(feature = "anthropic")]
+pub mod anthropic;
+#[cfg(feature = "cohere")]
+pub mod cohere;
+#[cfg(feature = "gemini")]
+pub mod gemini;
+pub mod mock;
+#[cfg(feature = "openai")]
+pub mod openai;
+#[cfg(feature = "openai_compatible")]
+pub mod openai_compatible;
+#[cfg(feature = "perplexity")]
+pub mod perplexity;
+pub mod replay;
+#[cfg(feature = "xai")]
+pub mod xai;
+
+use super::provider::{ProviderRegistry, RawResponse};
+use crate::error::CharacterfileError;
+use std::env;
+
+/// Hosted providers, each registered by the cargo feature of the same name. The features only
+/// gate these wrapper modules, rig's clients for every provider are always compiled.
+pub const FEATURES: &[(&str, bool)] = &[
+    ("anthropic", cfg!(feature = "anthropic")),
+    ("cohere", cfg!(feature = "cohere")),
+    ("gemini", cfg!(feature = "gemini")),
+    ("openai", cfg!(feature = "openai")),
+    ("openai_compatible", cfg!(feature = "openai_compatible")),
+    ("perplexity", cfg!(feature = "perplexity")),
+    ("xai", cfg!(feature = "xai")),
+];
+
+/// Returns whether `name` is a built-in provider left out of this build.
+pub fn is_disabled(name: &str) -> bool {
+    FEATURES
+        .iter()
+        .any(|(feature, enabled)| *feature == name && !enabled)
+}
+
+/// Registers every provider shipped with the crate and enabled in this build.
+pub fn register_builtin(registry: &mut ProviderRegistry) {
+    #[cfg(feature = "anthropic")]
+    registry.register(anthropic::NAME, anthropic::build);
+    #[cfg(feature = "cohere")]
+    registry.register(cohere::NAME, cohere::build);
+    #[cfg(feature = "gemini")]
+    registry.register(gemini::NAME, gemini::build);
+    #[cfg(feature = "openai")]
+    registry.register(openai::NAME, openai::build);
+    #[cfg(feature = "openai_compatible")]
+    registry.register(openai_compatible::NAME, openai_compatible::build);
+    #[cfg(feature = "perplexity")]
+    registry.register(perplexity::NAME, perplexity::build);
+    #[cfg(feature = "xai")]
+    registry.register(xai::NAME, xai::build);
+    registry.register(mock::NAME, mock::build);
+    registry.register(replay::NAME, replay::build);
+}
+
+fn env_var(key: &str) -> Result<String, anyhow::Error> {
+    env::var(key).map_err(|_| anyhow::anyhow!("{} not set", key))
+}
+
+/// The model set in `config.model`, falling back to the `key` environment variable.
+#[cfg(any(
+    feature = "anthropic",
+    feature = "cohere",
+    feature = "gemini",
+    feature = "openai",
+    feature = "perplexity",
+    feature = "xai"
+))]
+fn model_name(config: &crate::config::Config, key: &str) -> Result<String, anyhow::Error> {
+    match &config.model {
+        Some(model) => Ok(model.clone()),
+        None => env_var(key),
+    }
+}
+
+/// Recovers the concrete response type a provider produced.
+fn downcast<'a, T: 'static>(
+    response: &'a RawResponse,
+    provider: &str,
+) -> Result<&'a T, CharacterfileError> {
+    response.downcast_ref::<T>().ok_or_else(|| {
+        CharacterfileError::Parse(format!("Response was not produced by {}", provider))
+    })
+}
+
+fn empty(provider: &str) -> CharacterfileError {
+    CharacterfileError::EmptyResponse(format!("{} returned no content", provider))
+}
@@ -0,0 +1,72 @@
+use super::{downcast, empty, env_var, model_name};
+use crate::completion::provider::{Provider, RawResponse};
+use crate::completion::structured::{openai_json_schema, StructuredOutputParams};
+use crate::config::Config;
+use crate::error::CharacterfileError;
+use async_trait::async_trait;
+use rig::completion::{CompletionError, CompletionModel, CompletionRequest, CompletionResponse};
+use rig::providers::openai;
+use serde_json::Value;
+
+pub const NAME: &str = "openai";
+
+/// Serves both hosted OpenAI and any server speaking its chat completions API.
+pub struct OpenAIProvider {
+    pub(super) name: &'static str,
+    pub(super) model: openai::CompletionModel,
+}
+
+pub fn build(config: &Config) -> Result<Box<dyn Provider>, anyhow::Error> {
+    let api_key = env_var("OPENAI_API_KEY")?;
+    let model = model_name(config, "OPENAI_COMPLETION_MODEL")?;
+    let client = match &config.base_url {
+        Some(base_url) => openai::Client::from_url(&api_key, base_url),
+        None => openai::Client::new(&api_key),
+    };
+    Ok(Box::new(OpenAIProvider {
+        name: NAME,
+        model: client.completion_model(&model),
+    }))
+}
+
+#[async_trait]
+impl Provider for OpenAIProvider {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn model(&self) -> &str {
+        &self.model.model
+    }
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<RawResponse>, CompletionError> {
+        let response = self.model.completion(request).await?;
+        Ok(CompletionResponse {
+            choice: response.choice,
+            raw_response: Box::new(response.raw_response),
+        })
+    }
+
+    fn extract_text(&self, response: &RawResponse) -> Result<String, CharacterfileError> {
+        let response = downcast::<openai::CompletionResponse>(response, self.name)?;
+        let choice = response.choices.first().ok_or_else(|| empty(self.name))?;
+        if choice.finish_reason == "content_filter" {
+            return Err(CharacterfileError::SafetyBlocked(format!(
+                "{} content filter stopped the response",
+                self.name
+            )));
+        }
+        choice
+            .message
+            .content
+            .clone()
+            .ok_or_else(|| empty(self.name))
+    }
+
+    fn structured_output(&self, schema: &Value) -> StructuredOutputParams {
+        openai_json_schema(schema)
+    }
+}
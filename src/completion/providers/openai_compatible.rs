@@ -0,0 +1,24 @@
+use super::openai::OpenAIProvider;
+use crate::completion::provider::Provider;
+use crate::config::Config;
+use rig::providers::openai;
+use std::env;
+
+pub const NAME: &str = "openai_compatible";
+
+pub fn build(config: &Config) -> Result<Box<dyn Provider>, anyhow::Error> {
+    let settings = config
+        .openai_compatible
+        .clone()
+        .ok_or_else(|| anyhow::anyhow!("openai_compatible settings not set in config.json"))?;
+    // local servers usually ignore the key
+    let api_key = settings
+        .api_key
+        .or_else(|| env::var("OPENAI_COMPATIBLE_API_KEY").ok())
+        .unwrap_or_default();
+    let client = openai::Client::from_url(&api_key, &settings.base_url);
+    Ok(Box::new(OpenAIProvider {
+        name: NAME,
+        model: client.completion_model(config.model.as_ref().unwrap_or(&settings.model)),
+    }))
+}
@@ -0,0 +1,93 @@
+use super::{downcast, empty, env_var, model_name};
+use crate::completion::provider::{Provider, RawResponse};
+use crate::completion::structured::StructuredOutputParams;
+use crate::config::Config;
+use crate::error::CharacterfileError;
+use async_trait::async_trait;
+use rig::completion::{CompletionError, CompletionModel, CompletionRequest, CompletionResponse};
+use rig::providers::perplexity;
+use serde_json::{json, Value};
+
+pub const NAME: &str = "perplexity";
+
+pub struct PerplexityProvider {
+    model: perplexity::CompletionModel,
+}
+
+pub fn build(config: &Config) -> Result<Box<dyn Provider>, anyhow::Error> {
+    let api_key = env_var("PERPLEXITY_API_KEY")?;
+    let model = model_name(config, "PERPLEXITY_COMPLETION_MODEL")?;
+    let client = match &config.base_url {
+        Some(base_url) => perplexity::Client::from_url(&api_key, base_url),
+        None => perplexity::Client::new(&api_key),
+    };
+    Ok(Box::new(PerplexityProvider {
+        model: client.completion_model(&model),
+    }))
+}
+
+#[async_trait]
+impl Provider for PerplexityProvider {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn model(&self) -> &str {
+        &self.model.model
+    }
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<RawResponse>, CompletionError> {
+        let response = self.model.completion(request).await?;
+        Ok(CompletionResponse {
+            choice: response.choice,
+            raw_response: Box::new(response.raw_response),
+        })
+    }
+
+    fn extract_text(&self, response: &RawResponse) -> Result<String, CharacterfileError> {
+        let response = downcast::<perplexity::CompletionResponse>(response, "Perplexity")?;
+        let choice = response
+            .choices
+            .first()
+            .ok_or_else(|| empty("Perplexity"))?;
+        match choice.message.content.is_empty() {
+            true => Err(empty("Perplexity")),
+            false => Ok(choice.message.content.clone()),
+        }
+    }
+
+    fn structured_output(&self, schema: &Value) -> StructuredOutputParams {
+        StructuredOutputParams {
+            tools: vec![],
+            additional_params: Some(json!({
+                "response_format": { "type": "json_schema", "json_schema": { "schema": schema } }
+            })),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::character::Character;
+
+    #[test]
+    fn requests_the_json_schema_response_format() {
+        let provider = PerplexityProvider {
+            model: perplexity::Client::new("key").completion_model("sonar"),
+        };
+        let schema = Character::json_schema();
+        let params = provider.structured_output(&schema);
+
+        assert!(params.tools.is_empty());
+        assert_eq!(
+            params.additional_params,
+            Some(json!({
+                "response_format": { "type": "json_schema", "json_schema": { "schema": schema } }
+            }))
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use super::{downcast, env_var};
+use crate::completion::provider::{Provider, RawResponse};
+use crate::config::Config;
+use crate::error::CharacterfileError;
+use crate::transcript::{Transcript, TranscriptEntry};
+use async_trait::async_trait;
+use log::warn;
+use rig::completion::{CompletionError, CompletionRequest, CompletionResponse, ModelChoice};
+use std::sync::{Arc, Mutex};
+
+pub const NAME: &str = "replay";
+
+/// Provider serving the responses of a recorded transcript in order.
+#[derive(Clone)]
+pub struct ReplayProvider {
+    entries: Vec<TranscriptEntry>,
+    cursor: Arc<Mutex<usize>>,
+}
+
+impl ReplayProvider {
+    pub fn new(entries: Vec<TranscriptEntry>) -> Self {
+        Self {
+            entries,
+            cursor: Arc::new(Mutex::new(0)),
+        }
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, anyhow::Error> {
+        Ok(Self::new(Transcript::load(path)?))
+    }
+}
+
+/// Reads the transcript from `REPLAY_TRANSCRIPT_PATH`.
+pub fn build(_config: &Config) -> Result<Box<dyn Provider>, anyhow::Error> {
+    let transcript_path = env_var("REPLAY_TRANSCRIPT_PATH")?;
+    let provider = ReplayProvider::from_file(&transcript_path).map_err(|e| {
+        anyhow::anyhow!(
+            "Failed to load replay transcript {}: {}",
+            transcript_path,
+            e
+        )
+    })?;
+    Ok(Box::new(provider))
+}
+
+#[async_trait]
+impl Provider for ReplayProvider {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn model(&self) -> &str {
+        "transcript"
+    }
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<RawResponse>, CompletionError> {
+        let entry = {
+            let mut cursor = self.cursor.lock().unwrap();
+            let entry = self.entries.get(*cursor).cloned().ok_or_else(|| {
+                CompletionError::ResponseError(format!(
+                    "Transcript exhausted after {} entries",
+                    self.entries.len()
+                ))
+            })?;
+            *cursor += 1;
+            entry
+        };
+
+        if entry.prompt != request.prompt {
+            warn!(
+                "[REPLAY] Prompt differs from the transcript entry recorded at {}",
+                entry.timestamp
+            );
+        }
+        match (entry.response, entry.error) {
+            (Some(text), _) => Ok(CompletionResponse {
+                choice: ModelChoice::Message(text.clone()),
+                raw_response: Box::new(text),
+            }),
+            (None, Some(error)) => Err(CompletionError::ProviderError(error)),
+            (None, None) => Err(CompletionError::ResponseError(
+                "Transcript entry has no response".to_string(),
+            )),
+        }
+    }
+
+    fn extract_text(&self, response: &RawResponse) -> Result<String, CharacterfileError> {
+        Ok(downcast::<String>(response, "Replay")?.clone())
+    }
+}
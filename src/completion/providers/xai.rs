@@ -0,0 +1,65 @@
+use super::{downcast, empty, env_var, model_name};
+use crate::completion::provider::{Provider, RawResponse};
+use crate::completion::structured::{openai_json_schema, StructuredOutputParams};
+use crate::config::Config;
+use crate::error::CharacterfileError;
+use async_trait::async_trait;
+use log::warn;
+use rig::completion::{CompletionError, CompletionModel, CompletionRequest, CompletionResponse};
+use rig::providers::xai::{self, completion::xai_api_types};
+use serde_json::Value;
+
+pub const NAME: &str = "xai";
+
+pub struct XAIProvider {
+    model: xai::completion::CompletionModel,
+}
+
+pub fn build(config: &Config) -> Result<Box<dyn Provider>, anyhow::Error> {
+    let api_key = env_var("XAI_API_KEY")?;
+    let model = model_name(config, "XAI_COMPLETION_MODEL")?;
+    if config.base_url.is_some() {
+        warn!("[SETUP] XAI does not support a custom base_url, using the default endpoint");
+    }
+    let client = xai::Client::new(&api_key);
+    Ok(Box::new(XAIProvider {
+        model: client.completion_model(&model),
+    }))
+}
+
+#[async_trait]
+impl Provider for XAIProvider {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn model(&self) -> &str {
+        &self.model.model
+    }
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<RawResponse>, CompletionError> {
+        let response = self.model.completion(request).await?;
+        Ok(CompletionResponse {
+            choice: response.choice,
+            raw_response: Box::new(response.raw_response),
+        })
+    }
+
+    fn extract_text(&self, response: &RawResponse) -> Result<String, CharacterfileError> {
+        let response = downcast::<xai_api_types::CompletionResponse>(response, "XAI")?;
+        let choice = response.choices.first().ok_or_else(|| empty("XAI"))?;
+        if choice.finish_reason == "content_filter" {
+            return Err(CharacterfileError::SafetyBlocked(
+                "XAI content filter stopped the response".into(),
+            ));
+        }
+        choice.message.content.clone().ok_or_else(|| empty("XAI"))
+    }
+
+    fn structured_output(&self, schema: &Value) -> StructuredOutputParams {
+        openai_json_schema(schema)
+    }
+}
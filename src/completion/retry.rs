@@ -0,0 +1,106 @@
+use crate::error::CharacterfileError;
+use rand::Rng;
+use rig::completion::CompletionRequest;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// How provider calls are retried on rate limits, overloads and network failures.
+#[derive(Deserialize, Debug, Clone)]
+pub struct RetryPolicy {
+    /// Total attempts per request, including the first one.
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    #[serde(default = "default_base_delay_ms")]
+    pub base_delay_ms: u64,
+    #[serde(default = "default_max_delay_ms")]
+    pub max_delay_ms: u64,
+}
+
+fn default_max_attempts() -> u32 {
+    4
+}
+
+fn default_base_delay_ms() -> u64 {
+    1000
+}
+
+fn default_max_delay_ms() -> u64 {
+    30000
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: default_max_attempts(),
+            base_delay_ms: default_base_delay_ms(),
+            max_delay_ms: default_max_delay_ms(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retrying after the `attempt`th failure. A provider Retry-After hint wins,
+    /// otherwise the exponential backoff is jittered between half and the full delay. `None` when
+    /// the hint is longer than `max_delay_ms`, retrying sooner would only fail again.
+    pub fn delay(&self, attempt: u32, error: &CharacterfileError) -> Option<Duration> {
+        if let Some(retry_after) = error.retry_after() {
+            return (retry_after <= Duration::from_millis(self.max_delay_ms))
+                .then_some(retry_after);
+        }
+        let backoff = self
+            .base_delay_ms
+            .saturating_mul(2u64.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_delay_ms);
+        let jittered = backoff / 2 + rand::thread_rng().gen_range(0..=backoff / 2);
+        Some(Duration::from_millis(jittered))
+    }
+}
+
+/// `CompletionRequest` is not `Clone`, so retries rebuild it field by field.
+pub fn clone_request(request: &CompletionRequest) -> CompletionRequest {
+    CompletionRequest {
+        prompt: request.prompt.clone(),
+        preamble: request.preamble.clone(),
+        chat_history: request.chat_history.clone(),
+        documents: request.documents.clone(),
+        tools: request.tools.clone(),
+        temperature: request.temperature,
+        max_tokens: request.max_tokens,
+        additional_params: request.additional_params.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 4,
+            base_delay_ms: 1000,
+            max_delay_ms: 30000,
+        }
+    }
+
+    #[test]
+    fn follows_retry_after_hint_within_max_delay() {
+        let error = CharacterfileError::RateLimited("Please try again in 1.5s".to_string());
+        assert_eq!(policy().delay(1, &error), Some(Duration::from_millis(1500)));
+    }
+
+    #[test]
+    fn gives_up_on_retry_after_hint_beyond_max_delay() {
+        let error = CharacterfileError::RateLimited("Please try again in 3600s".to_string());
+        assert_eq!(policy().delay(1, &error), None);
+    }
+
+    #[test]
+    fn jitters_exponential_backoff_up_to_max_delay() {
+        let error = CharacterfileError::Overloaded("Overloaded".to_string());
+        for (attempt, full) in [(1, 1000), (2, 2000), (3, 4000), (10, 30000)] {
+            let delay = policy().delay(attempt, &error).unwrap();
+            assert!(delay >= Duration::from_millis(full / 2));
+            assert!(delay <= Duration::from_millis(full));
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use rig::completion::ToolDefinition;
+use serde_json::Value;
+
+#[derive(Default)]
+pub struct StructuredOutputParams {
+    pub tools: Vec<ToolDefinition>,
+    pub additional_params: Option<Value>,
+}
+
+/// Constrains a completion to a JSON Schema using the provider's native facility.
+pub trait StructuredOutput {
+    fn structured_output(&self, schema: &Value) -> StructuredOutputParams;
+}
+
+/// OpenAI style strict `json_schema` response format, shared by compatible APIs.
+#[cfg(any(feature = "openai", feature = "xai"))]
+pub fn openai_json_schema(schema: &Value) -> StructuredOutputParams {
+    StructuredOutputParams {
+        tools: vec![],
+        additional_params: Some(serde_json::json!({
+            "response_format": {
+                "type": "json_schema",
+                "json_schema": { "name": "character", "strict": true, "schema": schema }
+            }
+        })),
+    }
+}
+
+#[cfg(all(test, any(feature = "openai", feature = "xai")))]
+mod tests {
+    use super::*;
+    use crate::character::Character;
+    use serde_json::json;
+
+    #[test]
+    fn openai_schema_is_strict() {
+        let schema = Character::json_schema();
+        let params = openai_json_schema(&schema);
+
+        assert!(params.tools.is_empty());
+        assert_eq!(
+            params.additional_params,
+            Some(json!({
+                "response_format": {
+                    "type": "json_schema",
+                    "json_schema": { "name": "character", "strict": true, "schema": schema }
+                }
+            }))
+        );
+    }
+}
@@ -1,35 +1,177 @@
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
 use std::fs;
+use std::str::FromStr;
 
+use crate::character::Field;
+use crate::completion::{providers, RetryPolicy};
 use crate::consts::CONFIG_PATH;
+use crate::version::VersionHistory;
 
+/// Settings loaded from `config.json`.
 #[derive(Deserialize, Debug, Clone)]
 pub struct Config {
     pub completion_provider: CompletionProvider,
     pub output_file_name: String,
+    /// How many corrective re-prompts to send when a response fails validation.
+    #[serde(default = "default_validation_retries")]
+    pub validation_retries: usize,
+    /// Constrain responses to the character schema with the provider's structured output.
+    #[serde(default = "default_structured_output")]
+    pub structured_output: bool,
+    /// Backoff applied to rate limited, overloaded or failed provider calls.
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    /// Write every completion request and response to `out/transcripts/*.jsonl`.
+    #[serde(default)]
+    pub record_transcripts: bool,
+    /// Overrides the endpoint of hosted providers, e.g. to route through a proxy.
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// Overrides the provider's model, otherwise read from `<PROVIDER>_COMPLETION_MODEL`.
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Directory holding `characters/`, `sessions/`, `versions/`, `locks/`, `history/` and
+    /// `transcripts/`.
+    #[serde(default = "default_output_dir")]
+    pub output_dir: String,
+    /// Keep each character's conversation in `<output_dir>/sessions/` and resume it on the next run.
+    #[serde(default = "default_sessions")]
+    pub sessions: bool,
+    /// Show each proposed character in `chat`, `new` and `iterate` and ask before saving it.
+    #[serde(default)]
+    pub review: bool,
+    /// Whether iterations return the whole character or a patch against it.
+    #[serde(default)]
+    pub iteration: IterationMode,
+    /// Fields every character keeps unchanged across iterations, on top of its own locks.
+    #[serde(default)]
+    pub locked_fields: Vec<Field>,
+    /// Settings for the `openai_compatible` provider.
+    #[serde(default)]
+    pub openai_compatible: Option<OpenAICompatibleConfig>,
+}
+
+/// How [`crate::Generator::iterate`] asks the model for changes.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum IterationMode {
+    /// The model returns the whole character.
+    #[default]
+    Full,
+    /// The model returns a JSON Patch (RFC 6902) or merge patch (RFC 7396) applied locally,
+    /// leaving every field it does not mention untouched.
+    Patch,
+}
+
+/// A server implementing the OpenAI chat completions API (Ollama, llama.cpp, vLLM, LM Studio...).
+#[derive(Deserialize, Debug, Clone)]
+pub struct OpenAICompatibleConfig {
+    /// e.g. `http://localhost:11434/v1` for Ollama.
+    pub base_url: String,
+    pub model: String,
+    /// Falls back to `OPENAI_COMPATIBLE_API_KEY`, then to no key.
+    #[serde(default)]
+    pub api_key: Option<String>,
+}
+
+fn default_validation_retries() -> usize {
+    2
+}
+
+fn default_structured_output() -> bool {
+    true
+}
+
+fn default_sessions() -> bool {
+    true
+}
+
+fn default_output_dir() -> String {
+    "out".to_string()
 }
 
 impl Config {
+    /// Loads the configuration from [`CONFIG_PATH`].
     pub fn new() -> Result<Self, anyhow::Error> {
-        let config_content = fs::read_to_string(CONFIG_PATH)?;
+        Self::from_path(CONFIG_PATH)
+    }
+
+    pub fn from_path(path: &str) -> Result<Self, anyhow::Error> {
+        let config_content = fs::read_to_string(path)?;
         let config: Config = serde_json::from_str(&config_content)?;
         Ok(config)
     }
+
+    /// Directory characters are saved to.
+    pub fn characters_dir(&self) -> String {
+        format!("{}/characters", self.output_dir)
+    }
+
+    /// Where the character is saved on `branch`: the main branch is the output file, other
+    /// branches live under `<output_dir>/branches/<name>/`.
+    pub fn character_path(&self, branch: &str) -> String {
+        match branch == VersionHistory::MAIN {
+            true => format!("{}/{}", self.characters_dir(), self.output_file_name),
+            false => format!(
+                "{}/branches/{}/{}.json",
+                self.output_dir,
+                self.output_file_name.trim_end_matches(".json"),
+                branch
+            ),
+        }
+    }
+
+    /// Directory sessions are saved to.
+    pub fn sessions_dir(&self) -> String {
+        format!("{}/sessions", self.output_dir)
+    }
+
+    /// Directory field locks and pinned entries are saved to.
+    pub fn locks_dir(&self) -> String {
+        format!("{}/locks", self.output_dir)
+    }
+
+    /// Directory the input typed in interactive sessions is saved to, one file per character.
+    pub fn input_history_dir(&self) -> String {
+        format!("{}/history", self.output_dir)
+    }
+
+    /// Directory version histories are saved to.
+    pub fn versions_dir(&self) -> String {
+        format!("{}/versions", self.output_dir)
+    }
+
+    /// Directory transcripts are recorded to.
+    pub fn transcripts_dir(&self) -> String {
+        format!("{}/transcripts", self.output_dir)
+    }
 }
 
 // PROVIDERS
-#[derive(Deserialize, Debug, Clone)]
-pub enum CompletionProvider {
-    #[serde(rename = "anthropic")]
-    Anthropic,
-    #[serde(rename = "cohere")]
-    Cohere,
-    #[serde(rename = "gemini")]
-    Gemini,
-    #[serde(rename = "openai")]
-    OpenAI,
-    #[serde(rename = "perplexity")]
-    Perplexity,
-    #[serde(rename = "xai")]
-    XAI,
+/// Name of a provider registered in [`crate::completion::ProviderRegistry`], e.g. `"anthropic"`.
+#[derive(Debug, Clone)]
+pub struct CompletionProvider(pub String);
+
+impl<'de> Deserialize<'de> for CompletionProvider {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl FromStr for CompletionProvider {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        if providers::is_disabled(name) {
+            return Err(anyhow::anyhow!(
+                "completion provider `{name}` is disabled in this build, rebuild with `--features {name}`"
+            ));
+        }
+        Ok(CompletionProvider(name.to_string()))
+    }
 }
@@ -0,0 +1,343 @@
+use crate::character::{Character, Field};
+use colored::{ColoredString, Colorize};
+use std::collections::HashSet;
+
+/// A word kept, removed or added between two versions of a text.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WordDiff {
+    Same(String),
+    Removed(String),
+    Added(String),
+}
+
+/// How a single field changed between two characters.
+#[derive(Debug, Clone)]
+pub enum FieldChange {
+    Text {
+        field: Field,
+        words: Vec<WordDiff>,
+    },
+    List {
+        field: Field,
+        removed: Vec<String>,
+        added: Vec<String>,
+        /// Entries reworded rather than replaced, as word diffs from the old to the new entry.
+        changed: Vec<Vec<WordDiff>>,
+    },
+}
+
+/// Field by field comparison of two characters.
+#[derive(Debug, Clone)]
+pub struct CharacterDiff {
+    pub changes: Vec<FieldChange>,
+}
+
+impl CharacterDiff {
+    /// Entries sharing at least this share of their words are shown as reworded.
+    const SIMILARITY: f64 = 0.5;
+
+    pub fn new(old: &Character, new: &Character) -> Self {
+        let mut changes = vec![];
+        for field in Field::ALL {
+            if let (Some(old_text), Some(new_text)) = (old.text(field), new.text(field)) {
+                if old_text != new_text {
+                    changes.push(FieldChange::Text {
+                        field,
+                        words: diff_words(old_text, new_text),
+                    });
+                }
+                continue;
+            }
+            let (Some(old_entries), Some(new_entries)) = (old.list(field), new.list(field)) else {
+                continue;
+            };
+
+            let mut removed: Vec<String> = old_entries
+                .iter()
+                .filter(|entry| !new_entries.contains(entry))
+                .cloned()
+                .collect();
+            let mut added: Vec<String> = new_entries
+                .iter()
+                .filter(|entry| !old_entries.contains(entry))
+                .cloned()
+                .collect();
+
+            // pair up reworded entries so they read as edits instead of a removal and an addition
+            let mut changed = vec![];
+            removed.retain(|old_entry| {
+                let best = added
+                    .iter()
+                    .enumerate()
+                    .map(|(i, new_entry)| (i, similarity(old_entry, new_entry)))
+                    .filter(|(_, score)| *score >= Self::SIMILARITY)
+                    .max_by(|a, b| a.1.total_cmp(&b.1));
+                match best {
+                    Some((i, _)) => {
+                        let new_entry = added.remove(i);
+                        changed.push(diff_words(old_entry, &new_entry));
+                        false
+                    }
+                    None => true,
+                }
+            });
+
+            if !removed.is_empty() || !added.is_empty() || !changed.is_empty() {
+                changes.push(FieldChange::List {
+                    field,
+                    removed,
+                    added,
+                    changed,
+                });
+            }
+        }
+        CharacterDiff { changes }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Renders the diff for a terminal: removals in red, additions in green and rewordings in
+    /// yellow with the changed words highlighted. Falls back to [`CharacterDiff::render_plain`]
+    /// when colors are disabled.
+    pub fn render(&self) -> String {
+        self.render_with(colored::control::SHOULD_COLORIZE.should_colorize())
+    }
+
+    /// Renders the diff without colors, marking changed words as `[-removed-]` and `{+added+}`.
+    pub fn render_plain(&self) -> String {
+        self.render_with(false)
+    }
+
+    fn render_with(&self, colorize: bool) -> String {
+        let paint = |text: ColoredString| match colorize {
+            true => text,
+            false => text.clear(),
+        };
+        if self.is_empty() {
+            return paint("No differences".dimmed()).to_string();
+        }
+
+        let mut lines = vec![];
+        for change in self.changes.iter() {
+            match change {
+                FieldChange::Text { field, words } => {
+                    lines.push(format!("{}", paint(field.name().bold())));
+                    lines.push(format!(
+                        "  {} {}",
+                        paint("~".yellow()),
+                        render_words(words, colorize)
+                    ));
+                }
+                FieldChange::List {
+                    field,
+                    removed,
+                    added,
+                    changed,
+                } => {
+                    lines.push(format!("{}", paint(field.name().bold())));
+                    for entry in removed {
+                        lines.push(format!("  {} {}", paint("-".red()), paint(entry.red())));
+                    }
+                    for words in changed {
+                        lines.push(format!(
+                            "  {} {}",
+                            paint("~".yellow()),
+                            render_words(words, colorize)
+                        ));
+                    }
+                    for entry in added {
+                        lines.push(format!("  {} {}", paint("+".green()), paint(entry.green())));
+                    }
+                }
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+/// Highlights changed words, or marks them as `[-removed-]` and `{+added+}` without `colorize`.
+fn render_words(words: &[WordDiff], colorize: bool) -> String {
+    words
+        .iter()
+        .map(|word| match (word, colorize) {
+            (WordDiff::Same(word), _) => word.clone(),
+            (WordDiff::Removed(word), true) => word.red().strikethrough().to_string(),
+            (WordDiff::Added(word), true) => word.green().underline().to_string(),
+            (WordDiff::Removed(word), false) => format!("[-{}-]", word),
+            (WordDiff::Added(word), false) => format!("{{+{}+}}", word),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Share of distinct words two entries have in common.
+fn similarity(a: &str, b: &str) -> f64 {
+    let a: HashSet<String> = a.split_whitespace().map(str::to_lowercase).collect();
+    let b: HashSet<String> = b.split_whitespace().map(str::to_lowercase).collect();
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+/// Word level diff of `old` into `new` along their longest common subsequence.
+pub fn diff_words(old: &str, new: &str) -> Vec<WordDiff> {
+    let old: Vec<&str> = old.split_whitespace().collect();
+    let new: Vec<&str> = new.split_whitespace().collect();
+
+    // lcs[i][j] is the common subsequence length of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = match old[i] == new[j] {
+                true => lcs[i + 1][j + 1] + 1,
+                false => lcs[i + 1][j].max(lcs[i][j + 1]),
+            };
+        }
+    }
+
+    let mut words = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            words.push(WordDiff::Same(old[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            words.push(WordDiff::Removed(old[i].to_string()));
+            i += 1;
+        } else {
+            words.push(WordDiff::Added(new[j].to_string()));
+            j += 1;
+        }
+    }
+    words.extend(
+        old[i..]
+            .iter()
+            .map(|word| WordDiff::Removed(word.to_string())),
+    );
+    words.extend(
+        new[j..]
+            .iter()
+            .map(|word| WordDiff::Added(word.to_string())),
+    );
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn character() -> Character {
+        let mut character = Character::new(String::new());
+        character.alias = "Ayla".to_string();
+        character.bio = "A pilot who loves the sky.".to_string();
+        character.adjectives = vec!["bold".to_string(), "quiet".to_string()];
+        character.lore = vec!["flew forty missions over the sea".to_string()];
+        character
+    }
+
+    fn same(word: &str) -> WordDiff {
+        WordDiff::Same(word.to_string())
+    }
+
+    fn removed(word: &str) -> WordDiff {
+        WordDiff::Removed(word.to_string())
+    }
+
+    fn added(word: &str) -> WordDiff {
+        WordDiff::Added(word.to_string())
+    }
+
+    #[test]
+    fn diffs_words_along_common_subsequence() {
+        assert_eq!(
+            diff_words("a pilot who loves the sky", "a pilot who fears the sea"),
+            vec![
+                same("a"),
+                same("pilot"),
+                same("who"),
+                removed("loves"),
+                added("fears"),
+                same("the"),
+                removed("sky"),
+                added("sea"),
+            ]
+        );
+        assert_eq!(
+            diff_words("", "new words"),
+            vec![added("new"), added("words")]
+        );
+        assert_eq!(diff_words("old", ""), vec![removed("old")]);
+        assert!(diff_words("", "").is_empty());
+    }
+
+    #[test]
+    fn finds_no_changes_between_equal_characters() {
+        assert!(CharacterDiff::new(&character(), &character()).is_empty());
+    }
+
+    #[test]
+    fn reports_text_and_list_changes() {
+        let old = character();
+        let mut new = character();
+        new.bio = "A pilot who fears the sky.".to_string();
+        new.adjectives = vec!["bold".to_string(), "loud".to_string()];
+        new.lore = vec!["flew fifty missions over the sea".to_string()];
+
+        let diff = CharacterDiff::new(&old, &new);
+        assert_eq!(diff.changes.len(), 3);
+        match &diff.changes[0] {
+            FieldChange::Text { field, words } => {
+                assert_eq!(*field, Field::Bio);
+                assert!(words.contains(&removed("loves")));
+                assert!(words.contains(&added("fears")));
+            }
+            change => panic!("unexpected change {:?}", change),
+        }
+        match &diff.changes[1] {
+            FieldChange::List {
+                field,
+                removed,
+                added,
+                changed,
+            } => {
+                assert_eq!(*field, Field::Adjectives);
+                assert_eq!(removed, &vec!["quiet".to_string()]);
+                assert_eq!(added, &vec!["loud".to_string()]);
+                assert!(changed.is_empty());
+            }
+            change => panic!("unexpected change {:?}", change),
+        }
+        // a mostly identical entry reads as reworded rather than removed and added
+        match &diff.changes[2] {
+            FieldChange::List {
+                field,
+                removed,
+                added,
+                changed,
+            } => {
+                assert_eq!(*field, Field::Lore);
+                assert!(removed.is_empty() && added.is_empty());
+                assert_eq!(changed.len(), 1);
+            }
+            change => panic!("unexpected change {:?}", change),
+        }
+    }
+
+    #[test]
+    fn renders_markers_without_colors() {
+        let old = character();
+        let mut new = character();
+        new.bio = "A pilot who fears the sky.".to_string();
+        new.adjectives.push("loud".to_string());
+
+        assert_eq!(
+            CharacterDiff::new(&old, &new).render_plain(),
+            "bio\n  ~ A pilot who [-loves-] {+fears+} the sky.\nadjectives\n  + loud"
+        );
+    }
+}
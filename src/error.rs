@@ -0,0 +1,331 @@
+use rig::completion::CompletionError;
+use serde_json::Value;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Recoverable failures of a provider call, each with a message telling the user what to do next.
+#[derive(Debug, Error)]
+pub enum CharacterfileError {
+    #[error("Network error: {0} (check your connection and try again)")]
+    Network(String),
+    #[error("Authentication failed: {0} (check the provider API key in .env)")]
+    Auth(String),
+    #[error("Rate limited: {0} (wait a moment and try again)")]
+    RateLimited(String),
+    #[error("Quota exhausted: {0} (check the plan and billing of the provider account)")]
+    QuotaExceeded(String),
+    #[error("Provider overloaded: {0} (wait a moment and try again)")]
+    Overloaded(String),
+    #[error("Empty response: {0} (try again or rephrase the prompt)")]
+    EmptyResponse(String),
+    #[error("Blocked by safety filters: {0} (rephrase the prompt)")]
+    SafetyBlocked(String),
+    #[error("Failed to parse provider response: {0}")]
+    Parse(String),
+    #[error("Provider error: {0}")]
+    Provider(String),
+}
+
+impl From<CompletionError> for CharacterfileError {
+    fn from(error: CompletionError) -> Self {
+        match error {
+            CompletionError::HttpError(e) => match e.status().map(|status| status.as_u16()) {
+                Some(401) | Some(403) => Self::Auth(e.to_string()),
+                Some(429) => Self::RateLimited(e.to_string()),
+                Some(500..=599) => Self::Overloaded(e.to_string()),
+                Some(_) => Self::Provider(e.to_string()),
+                None if e.is_decode() => Self::Parse(e.to_string()),
+                None => Self::Network(e.to_string()),
+            },
+            CompletionError::JsonError(e) => Self::Parse(e.to_string()),
+            CompletionError::RequestError(e) => Self::Provider(e.to_string()),
+            CompletionError::ResponseError(message) => Self::EmptyResponse(message),
+            CompletionError::ProviderError(message) => Self::classify(message),
+        }
+    }
+}
+
+impl CharacterfileError {
+    /// Whether the same request may succeed if sent again later.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::Network(_) | Self::RateLimited(_) | Self::Overloaded(_)
+        )
+    }
+
+    /// Wait time requested by the provider, e.g. "Please try again in 1.5s" or
+    /// `"retryDelay": "30s"`.
+    pub fn retry_after(&self) -> Option<Duration> {
+        let message = match self {
+            Self::RateLimited(message) | Self::Overloaded(message) => message.to_lowercase(),
+            _ => return None,
+        };
+        ["retry after", "retry-after", "try again in", "retrydelay"]
+            .iter()
+            .find_map(|hint| {
+                let rest = &message[message.find(hint)? + hint.len()..];
+                let rest = rest.trim_start_matches(|c: char| !c.is_ascii_digit());
+                let number_len = rest
+                    .find(|c: char| !c.is_ascii_digit() && c != '.')
+                    .unwrap_or(rest.len());
+                let number: f64 = rest[..number_len].parse().ok()?;
+                let seconds = match rest[number_len..].trim_start() {
+                    unit if unit.starts_with("ms") => number / 1000.0,
+                    unit if unit.starts_with('m') => number * 60.0,
+                    _ => number,
+                };
+                Some(Duration::from_secs_f64(seconds))
+            })
+    }
+
+    /// Sorts a provider error body into a variant. Providers only hand back the body, so the
+    /// error `type`, `code` and `status` of its JSON are used when they are known, and the
+    /// wording of the message otherwise.
+    fn classify(message: String) -> Self {
+        let lower = message.to_lowercase();
+        let mentions = |needles: &[&str]| needles.iter().any(|needle| lower.contains(needle));
+        // Gemini reports an exhausted quota with the same RESOURCE_EXHAUSTED status as rate
+        // limits, only the wording tells them apart
+        if mentions(&["insufficient quota", "exceeded your current quota"]) {
+            return Self::QuotaExceeded(message);
+        }
+        if let Some(error) = Self::from_codes(&error_codes(&message), &message) {
+            return error;
+        }
+
+        // whole tokens only, so request ids and model dates do not read as status codes
+        let statuses: Vec<&str> = lower
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|token| token.len() == 3 && token.chars().all(|c| c.is_ascii_digit()))
+            .collect();
+        let status = |codes: &[&str]| statuses.iter().any(|status| codes.contains(status));
+        if mentions(&["rate limit", "too many requests"]) || status(&["429"]) {
+            Self::RateLimited(message)
+        } else if mentions(&["overloaded", "service unavailable"])
+            || status(&["500", "502", "503", "504", "529"])
+        {
+            Self::Overloaded(message)
+        } else if mentions(&[
+            "unauthorized",
+            "invalid api key",
+            "invalid x-api-key",
+            "incorrect api key",
+        ]) || status(&["401", "403"])
+        {
+            Self::Auth(message)
+        } else if mentions(&["safety", "content policy"]) {
+            Self::SafetyBlocked(message)
+        } else {
+            Self::Provider(message)
+        }
+    }
+
+    fn from_codes(codes: &[String], message: &str) -> Option<Self> {
+        let has = |known: &[&str]| codes.iter().any(|code| known.contains(&code.as_str()));
+        let message = message.to_string();
+        let error = if has(&[
+            "insufficient_quota",
+            "billing_hard_limit_reached",
+            "quota_exceeded",
+        ]) {
+            Self::QuotaExceeded(message)
+        } else if has(&[
+            "rate_limit_error",
+            "rate_limit_exceeded",
+            "resource_exhausted",
+            "too_many_requests",
+            "429",
+        ]) {
+            Self::RateLimited(message)
+        } else if has(&[
+            "overloaded_error",
+            "api_error",
+            "server_error",
+            "internal",
+            "unavailable",
+            "500",
+            "502",
+            "503",
+            "504",
+            "529",
+        ]) {
+            Self::Overloaded(message)
+        } else if has(&[
+            "authentication_error",
+            "permission_error",
+            "invalid_api_key",
+            "unauthenticated",
+            "permission_denied",
+            "401",
+            "403",
+        ]) {
+            Self::Auth(message)
+        } else if has(&["content_filter", "content_policy_violation"]) {
+            Self::SafetyBlocked(message)
+        } else if has(&[
+            "invalid_request_error",
+            "not_found_error",
+            "model_not_found",
+        ]) {
+            Self::Provider(message)
+        } else {
+            return None;
+        };
+        Some(error)
+    }
+}
+
+/// Lowercased `type`, `code` and `status` values of a JSON error body, including those nested in
+/// its `error` object, e.g. `overloaded_error` from Anthropic or `insufficient_quota` from OpenAI.
+fn error_codes(message: &str) -> Vec<String> {
+    let Some(start) = message.find('{') else {
+        return vec![];
+    };
+    let mut stream = serde_json::Deserializer::from_str(&message[start..]).into_iter::<Value>();
+    let Some(Ok(body)) = stream.next() else {
+        return vec![];
+    };
+
+    let mut codes = vec![];
+    for object in [Some(&body), body.get("error")].into_iter().flatten() {
+        for key in ["type", "code", "status"] {
+            match object.get(key) {
+                Some(Value::String(code)) => codes.push(code.to_lowercase()),
+                Some(Value::Number(code)) => codes.push(code.to_string()),
+                _ => {}
+            }
+        }
+    }
+    // Anthropic wraps every error in `"type": "error"`
+    codes.retain(|code| code != "error");
+    codes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classify(message: &str) -> CharacterfileError {
+        CharacterfileError::classify(message.to_string())
+    }
+
+    fn retry_after(message: &str) -> Option<Duration> {
+        CharacterfileError::RateLimited(message.to_string()).retry_after()
+    }
+
+    #[test]
+    fn parses_retry_after_hints() {
+        assert_eq!(
+            retry_after("Please try again in 1.5s."),
+            Some(Duration::from_millis(1500))
+        );
+        assert_eq!(
+            retry_after("Please try again in 250ms"),
+            Some(Duration::from_millis(250))
+        );
+        assert_eq!(
+            retry_after(
+                r#"{"@type": "type.googleapis.com/google.rpc.RetryInfo", "retryDelay": "30s"}"#
+            ),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            retry_after("Rate limit reached, try again in 2m"),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(retry_after("Retry-After: 7"), Some(Duration::from_secs(7)));
+        assert_eq!(retry_after("Rate limit reached"), None);
+        assert_eq!(
+            CharacterfileError::Provider("try again in 1s".to_string()).retry_after(),
+            None
+        );
+    }
+
+    #[test]
+    fn classifies_anthropic_error_types() {
+        let overloaded =
+            r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#;
+        assert!(matches!(
+            classify(overloaded),
+            CharacterfileError::Overloaded(_)
+        ));
+
+        let rate_limited = r#"{"type":"error","error":{"type":"rate_limit_error","message":"Number of request tokens has exceeded your per-minute rate limit"}}"#;
+        assert!(matches!(
+            classify(rate_limited),
+            CharacterfileError::RateLimited(_)
+        ));
+
+        let auth = r#"{"type":"error","error":{"type":"authentication_error","message":"invalid x-api-key"}}"#;
+        assert!(matches!(classify(auth), CharacterfileError::Auth(_)));
+    }
+
+    #[test]
+    fn ignores_digits_inside_request_ids() {
+        let invalid = r#"{"type":"error","error":{"type":"invalid_request_error","message":"max_tokens: 90000 > 8192"},"request_id":"req_011CWx529ab"}"#;
+        let error = classify(invalid);
+        assert!(matches!(error, CharacterfileError::Provider(_)));
+        assert!(!error.is_retryable());
+    }
+
+    #[test]
+    fn ignores_digits_inside_model_names() {
+        let error = classify("model claude-3-5-sonnet-20240401 not found");
+        assert!(matches!(error, CharacterfileError::Provider(_)));
+    }
+
+    #[test]
+    fn separates_openai_quota_from_rate_limits() {
+        let quota = r#"{"error":{"message":"You exceeded your current quota, please check your plan and billing details.","type":"insufficient_quota","param":null,"code":"insufficient_quota"}}"#;
+        let error = classify(quota);
+        assert!(matches!(error, CharacterfileError::QuotaExceeded(_)));
+        assert!(!error.is_retryable());
+
+        let rate_limited = r#"{"error":{"message":"Rate limit reached for gpt-4o. Please try again in 1.5s.","type":"requests","param":null,"code":"rate_limit_exceeded"}}"#;
+        let error = classify(rate_limited);
+        assert!(matches!(error, CharacterfileError::RateLimited(_)));
+        assert!(error.is_retryable());
+    }
+
+    #[test]
+    fn classifies_gemini_status() {
+        let exhausted = r#"{"error":{"code":429,"message":"Resource has been exhausted","status":"RESOURCE_EXHAUSTED"}}"#;
+        assert!(matches!(
+            classify(exhausted),
+            CharacterfileError::RateLimited(_)
+        ));
+
+        let quota = r#"{"error":{"code":429,"message":"You exceeded your current quota, please check your plan and billing details. For more information on this error, head to: https://ai.google.dev/gemini-api/docs/rate-limits.","status":"RESOURCE_EXHAUSTED","details":[{"@type":"type.googleapis.com/google.rpc.QuotaFailure","violations":[{"quotaMetric":"generativelanguage.googleapis.com/generate_content_free_tier_requests","quotaId":"GenerateRequestsPerDayPerProjectPerModel-FreeTier","quotaDimensions":{"location":"global","model":"gemini-2.0-flash"},"quotaValue":"200"}]},{"@type":"type.googleapis.com/google.rpc.RetryInfo","retryDelay":"25s"}]}}"#;
+        let error = classify(quota);
+        assert!(matches!(error, CharacterfileError::QuotaExceeded(_)));
+        assert!(!error.is_retryable());
+
+        let unavailable =
+            r#"{"error":{"code":503,"message":"The model is overloaded.","status":"UNAVAILABLE"}}"#;
+        assert!(matches!(
+            classify(unavailable),
+            CharacterfileError::Overloaded(_)
+        ));
+    }
+
+    #[test]
+    fn falls_back_to_wording_and_whole_status_tokens() {
+        assert!(matches!(
+            classify("Too many requests, slow down"),
+            CharacterfileError::RateLimited(_)
+        ));
+        assert!(matches!(
+            classify("HTTP 503 Service Unavailable"),
+            CharacterfileError::Overloaded(_)
+        ));
+        assert!(matches!(
+            classify("status 401: unauthorized"),
+            CharacterfileError::Auth(_)
+        ));
+        assert!(matches!(
+            classify(r#"{"message":"something odd happened"}"#),
+            CharacterfileError::Provider(_)
+        ));
+    }
+}
@@ -1,355 +1,852 @@
-use crate::character::Character;
-use crate::completion::{Agent, CompletionResponseEnum};
-use crate::config::Config;
+use crate::character::{Character, Field};
+use crate::completion::{Agent, ProviderResponse, StructuredOutput};
+use crate::config::{Config, IterationMode};
 use crate::input::Input;
-use log::{error, info, warn};
-use rig::completion::{Document, Message};
-use std::collections::{HashMap, VecDeque};
-use std::io::{self, Write};
+use crate::lock::Locks;
+use crate::patch::CharacterPatch;
+use crate::repair::repair_json;
+use crate::session::Session;
+use crate::transcript::Transcript;
+use crate::version::{Version, VersionHistory};
+use log::{info, warn};
+use rig::completion::{CompletionRequestBuilder, Document, Message};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// A validated character returned by the model that is not saved yet, see [`Generator::commit`].
+pub struct Proposal {
+    pub instruction: String,
+    pub character: Character,
+    /// Assistant turn added to the session once committed.
+    pub response: String,
+    pub provider: &'static str,
+    pub model: String,
+    /// Character saved before the proposal, `None` when creating the first one.
+    pub previous: Option<Character>,
+}
+
+impl Proposal {
+    /// Keeps the proposed value of `fields` only, every other field stays as saved before.
+    pub fn keep_fields(&mut self, fields: &[Field]) -> Result<(), anyhow::Error> {
+        let previous = self
+            .previous
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No saved character to keep the other fields from"))?;
+        for field in Field::ALL {
+            if !fields.contains(&field) {
+                self.character.copy_field(field, previous);
+            }
+        }
+        self.response = self.character.to_json_string()?;
+        Ok(())
+    }
+
+    /// Replaces the proposed character, e.g. after editing it by hand.
+    pub fn replace(&mut self, character: Character) -> Result<(), anyhow::Error> {
+        self.response = character.to_json_string()?;
+        self.character = Character {
+            path: std::mem::take(&mut self.character.path),
+            ..character
+        };
+        Ok(())
+    }
+}
+
+/// A focused change to a single field, see [`Generator::propose_field`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldEdit {
+    /// Writes the field anew.
+    Regenerate,
+    /// Adds this many entries to a list field (3 by default), or detail to a text field.
+    Expand(Option<usize>),
+    /// Tightens the field into fewer, shorter entries or a shorter text.
+    Condense,
+}
+
+impl FieldEdit {
+    const EXPAND_ENTRIES: usize = 3;
+
+    /// What the model is asked to do with `field`.
+    fn task(&self, field: Field) -> String {
+        match (self, field.is_list()) {
+            (FieldEdit::Regenerate, true) => format!("Write a completely new set of {field} entries, different from the current ones"),
+            (FieldEdit::Regenerate, false) => format!("Write a completely new {field}, different from the current one"),
+            (FieldEdit::Expand(count), true) => format!("Keep every current {field} entry verbatim and add {} new ones that do not repeat them", count.unwrap_or(Self::EXPAND_ENTRIES)),
+            (FieldEdit::Expand(Some(count)), false) => format!("Expand the {field} with more detail to about {count} sentences"),
+            (FieldEdit::Expand(None), false) => format!("Expand the {field} with more detail"),
+            (FieldEdit::Condense, true) => format!("Condense the {field} into fewer, tighter entries, merging overlapping ones and dropping the weakest"),
+            (FieldEdit::Condense, false) => format!("Condense the {field} into a shorter, tighter version"),
+        }
+    }
+
+    /// Rejects edits that cannot be asked for: the alias always comes from the input and an
+    /// expansion needs at least one entry or sentence.
+    fn validate(&self, field: Field) -> Result<(), anyhow::Error> {
+        if field == Field::Alias {
+            return Err(anyhow::anyhow!(
+                "The alias is the name of the input, change it there instead"
+            ));
+        }
+        if *self == FieldEdit::Expand(Some(0)) {
+            return Err(anyhow::anyhow!("Expand by a count of at least 1"));
+        }
+        Ok(())
+    }
+
+    /// Rejects results that did not do what was asked, e.g. an expansion dropping entries.
+    fn check(
+        &self,
+        field: Field,
+        before: &Character,
+        after: &Character,
+    ) -> Result<(), anyhow::Error> {
+        let (Some(before), Some(after)) = (before.list(field), after.list(field)) else {
+            return Ok(());
+        };
+        match self {
+            FieldEdit::Expand(_) if after.len() <= before.len() => Err(anyhow::anyhow!(
+                "Expected more than the {} current {} entries, got {}",
+                before.len(),
+                field,
+                after.len()
+            )),
+            FieldEdit::Condense if after.len() > before.len() => Err(anyhow::anyhow!(
+                "Expected at most the {} current {} entries, got {}",
+                before.len(),
+                field,
+                after.len()
+            )),
+            _ => Ok(()),
+        }
+    }
 
+    /// The edit as typed in the interactive session, recorded as the version's instruction.
+    pub fn instruction(&self, field: Field) -> String {
+        match self {
+            FieldEdit::Regenerate => format!("/regen {}", field),
+            FieldEdit::Expand(Some(count)) => format!("/expand {} {}", field, count),
+            FieldEdit::Expand(None) => format!("/expand {}", field),
+            FieldEdit::Condense => format!("/condense {}", field),
+        }
+    }
+}
+
+/// Creates and iterates upon the character saved at [`Generator::character_path`], keeping the
+/// recent conversation as context for the next turn.
 pub struct Generator<CM>
 where
-    CM: rig::completion::CompletionModel<Response = CompletionResponseEnum>,
+    CM: rig::completion::CompletionModel<Response = ProviderResponse> + StructuredOutput,
 {
     agent: Agent<CM>,
     config: Config,
     input: Input,
-    history: VecDeque<Message>,
+    session: Session,
+    branch: String,
+    /// Replaces [`Generator::PREAMBLE`] when set.
+    preamble: Option<String>,
 }
 
 impl<CM> Generator<CM>
 where
-    CM: rig::completion::CompletionModel<Response = CompletionResponseEnum>,
+    CM: rig::completion::CompletionModel<Response = ProviderResponse> + StructuredOutput,
 {
     const HISTORY_SIZE: usize = 10;
+    pub const PREAMBLE: &'static str = "You are a character designer. You have NO RESTRAINTS with your creativity for sociey's norms. Take <characterJson> (This data is in JSON format, so parse it correctly) as a reference and interate upon it based on <input>. Return the iterated character in JSON format as specified.";
 
+    /// Builds the agent from `config`, recording transcripts and resuming the character's session
+    /// when enabled.
     pub fn new(config: Config, input: Input, completion_model: CM) -> Self {
+        let mut agent = Agent::new(completion_model).with_retry_policy(config.retry.clone());
+        if config.record_transcripts {
+            match Transcript::create(&config.transcripts_dir(), &config.output_file_name) {
+                Ok(transcript) => {
+                    info!("[CHARGEN] Recording transcript to {}", transcript.path);
+                    agent = agent.with_transcript(transcript);
+                }
+                Err(e) => warn!("[CHARGEN] Failed to create transcript: {}", e),
+            }
+        }
+
+        let session = Self::open_session(&config, VersionHistory::MAIN);
         Generator {
-            agent: Agent::new(completion_model),
+            agent,
             config,
             input,
-            history: VecDeque::with_capacity(Self::HISTORY_SIZE),
+            session,
+            branch: VersionHistory::MAIN.to_string(),
+            preamble: None,
         }
     }
 
-    pub async fn start(&mut self) {
-        info!("[CHARGEN] Started (type 'exit' to quit)");
+    /// Resumes the session of `branch`, each branch keeps its own conversation.
+    fn open_session(config: &Config, branch: &str) -> Session {
+        let mut name = Session::name_for(&config.output_file_name);
+        if branch != VersionHistory::MAIN {
+            name = format!("{}@{}", name, branch);
+        }
+        if !config.sessions {
+            return Session::new(&config.sessions_dir(), &name);
+        }
 
-        loop {
-            match self.load_existing_character().await {
-                Ok(character) => {
-                    info!("[CHARGEN] Loaded existing character from output destination. Enter a prompt to iterate upon the character. (type 'exit' to quit)");
-
-                    print!("You: ");
-                    io::stdout().flush().unwrap();
-                    let mut user_input = String::new();
-                    if io::stdin().read_line(&mut user_input).is_err() {
-                        error!("[CHARGEN] Failed to read input");
-                        continue;
-                    }
+        match Session::load_or_new(&config.sessions_dir(), &name) {
+            Ok(session) => {
+                if !session.history.is_empty() {
+                    info!(
+                        "[CHARGEN] Resumed session {} ({} messages)",
+                        session.name,
+                        session.history.len()
+                    );
+                }
+                session
+            }
+            Err(e) => {
+                warn!("[CHARGEN] Failed to resume session {}: {}", name, e);
+                Session::new(&config.sessions_dir(), &name)
+            }
+        }
+    }
 
-                    let user_input = user_input.trim();
+    /// Iterates upon the character saved at the output destination following `instruction`,
+    /// saves the result and returns it.
+    pub async fn iterate(&mut self, instruction: &str) -> Result<Character, anyhow::Error> {
+        let proposal = self.propose_iterate(instruction).await?;
+        self.commit(proposal)
+    }
 
-                    // models cant take empty messages
-                    if user_input.is_empty() {
-                        continue;
-                    }
+    /// Generates a fresh character from the template following `instruction`,
+    /// saves it to the output destination and returns it.
+    pub async fn create(&mut self, instruction: &str) -> Result<Character, anyhow::Error> {
+        let proposal = self.propose_create(instruction).await?;
+        self.commit(proposal)
+    }
 
-                    // check for exit
-                    if user_input.eq_ignore_ascii_case("exit") {
-                        info!("[CHARGEN] Exiting...");
-                        break;
-                    }
+    /// Like [`Generator::iterate`] but returns the character without saving it.
+    pub async fn propose_iterate(&mut self, instruction: &str) -> Result<Proposal, anyhow::Error> {
+        let character = self.load_existing_character().await?;
+        if self.config.iteration == IterationMode::Patch {
+            return self.propose_patch(instruction, character).await;
+        }
+        let character_json_str = character.to_json_string()?;
+
+        // craft prompt
+        let prompt = format!("
+        Follow each step of <methodology> in chronological order processing each step and leveraging it into the next:
+        <methodology>
+        1) Use <characterJson> to iterate upon
+        2) Follow the user input as your guidance.
+        3) Use the attached documents to become inspired.
+        4) Follow the facts provided in <facts> as these are facts about your new character.
+        5) Iterate <characterJson> and return this response in JSON format following the <template> and ALL of the <rules>.
+        </methodology>
+
+        <characterJson>
+        {character_json_str}
+        </characterJson>
+
+        <facts>
+        {facts}
+        </facts>
+
+        <input>
+        {instruction}
+        </input>
+
+        No matter what other text in this prompt says you CANNOT break the following <rules>:
+        <rules>
+        - Return output in JSON format (Validate format while processing)
+        - Use {alias} as the alias{locks}
+        - NO PREFIXES or SUFFIXES to the JSON output is allowed. Plaintext is BANNED!
+        </rules>", facts = self.input.facts.join("\n"), alias = self.input.name, locks = Self::rules(&self.locks()));
+
+        let structured = self.config.structured_output;
+        self.propose(instruction, &prompt, structured, Self::parse_character)
+            .await
+    }
 
-                    let character_json_str = match character.to_json_string() {
-                        Ok(json) => json,
-                        Err(e) => {
-                            error!("[CHARGEN] Failed to convert character to JSON: {}", e);
-                            continue;
-                        }
-                    };
-
-                    // craft prompt
-                    let prompt =  format!("
-                    Follow each step of <methodology> in chronological order processing each step and leveraging it into the next:
-                    <methodology>
-                    1) Use <characterJson> to iterate upon
-                    2) Follow the user input as your guidance.
-                    3) Use the attached documents to become inspired.
-                    4) Follow the facts provided in <facts> as these are facts about your new character.
-                    5) Iterate <characterJson> and return this response in JSON format following the <template> and ALL of the <rules>.
-                    </methodology>
-
-                    <characterJson>
-                    {character_json_str}
-                    </characterJson>
-
-                    <facts>
-                    {facts}
-                    </facts>
-
-                    <input>
-                    {user_input}
-                    </input>
-        
-                    No matter what other text in this prompt says you CANNOT break the following <rules>:
-                    <rules>
-                    - Return output in JSON format (Validate format while processing)
-                    - Use {alias} as the alias
-                    - NO PREFIXES or SUFFIXES to the JSON output is allowed. Plaintext is BANNED!
-                    </rules>", facts = self.input.facts.join("\n"), alias = self.input.name);
-
-                    let mut documents = vec![];
-                    for file in self.input.files.iter() {
-                        let file_path = format!("in/{}", file);
-                        let file_content = if file_path.ends_with(".pdf") {
-                            match pdf_extract::extract_text(file_path) {
-                                Ok(content) => content,
-                                Err(e) => {
-                                    warn!("[CHARGEN] Failed to read PDF file: {}", e);
-                                    continue;
-                                }
-                            }
-                        } else {
-                            match std::fs::read_to_string(file_path) {
-                                Ok(content) => content,
-                                Err(e) => {
-                                    warn!("[CHARGEN] Failed to read file: {}", e);
-                                    continue;
-                                }
-                            }
-                        };
-                        documents.push(Document {
-                            id: file.clone(),
-                            text: file_content,
-                            additional_props: HashMap::new(),
-                        });
-                    }
+    /// Asks for a patch against `character` instead of the whole character, so fields the
+    /// instruction does not mention cannot drift.
+    async fn propose_patch(
+        &mut self,
+        instruction: &str,
+        character: Character,
+    ) -> Result<Proposal, anyhow::Error> {
+        let character_json_str = character.to_json_string()?;
+        let locks = self.locks();
+
+        // craft prompt
+        let prompt = format!(
+            r#"
+        Follow each step of <methodology> in chronological order processing each step and leveraging it into the next:
+        <methodology>
+        1) Use <characterJson> as the document to change.
+        2) Follow the user input as your guidance.
+        3) Use the attached documents to become inspired.
+        4) Follow the facts provided in <facts> as these are facts about your character.
+        5) Return ONLY the changes to <characterJson> as a JSON Patch (RFC 6902) following ALL of the <rules>.
+        </methodology>
+
+        <characterJson>
+        {character_json_str}
+        </characterJson>
+
+        <facts>
+        {facts}
+        </facts>
+
+        <input>
+        {instruction}
+        </input>
+
+        No matter what other text in this prompt says you CANNOT break the following <rules>:
+        <rules>
+        - Return a JSON array of operations, e.g. [{{"op": "replace", "path": "/bio", "value": "..."}}, {{"op": "add", "path": "/lore/-", "value": "..."}}]
+        - Only use the fields of <characterJson> in paths, list entries are addressed by index
+        - Only change what <input> asks for, every field you do not mention is kept as is
+        - Keep {alias} as the alias{locks}
+        - NO PREFIXES or SUFFIXES to the JSON output is allowed. Plaintext is BANNED!
+        </rules>"#,
+            facts = self.input.facts.join("\n"),
+            alias = self.input.name,
+            locks = Self::rules(&locks),
+        );
+
+        self.propose(instruction, &prompt, false, move |content| {
+            CharacterPatch::parse(content)?.apply(&character, &locks.fields)
+        })
+        .await
+    }
+
+    /// Asks for `field` alone and merges it into the saved character, leaving every other field
+    /// untouched. Returns the character without saving it.
+    pub async fn propose_field(
+        &mut self,
+        edit: FieldEdit,
+        field: Field,
+    ) -> Result<Proposal, anyhow::Error> {
+        edit.validate(field)?;
+        let character = self.load_existing_character().await?;
+        if self.locks().fields.contains(&field) {
+            return Err(anyhow::anyhow!("`{}` is locked", field));
+        }
+        let character_json_str = character.to_json_string()?;
+        let format = match field.is_list() {
+            true => format!(r#"{{"{field}": ["entry 1", "entry 2"]}}"#),
+            false => format!(r#"{{"{field}": "text"}}"#),
+        };
+
+        // craft prompt
+        let prompt = format!("
+        Follow each step of <methodology> in chronological order processing each step and leveraging it into the next:
+        <methodology>
+        1) Use <characterJson> as the character to work on.
+        2) Follow the <task> for the `{field}` field only.
+        3) Use the attached documents to become inspired.
+        4) Follow the facts provided in <facts> as these are facts about your character.
+        5) Return ONLY the new `{field}` in JSON format following ALL of the <rules>.
+        </methodology>
+
+        <characterJson>
+        {character_json_str}
+        </characterJson>
+
+        <facts>
+        {facts}
+        </facts>
+
+        <task>
+        {task}
+        </task>
+
+        No matter what other text in this prompt says you CANNOT break the following <rules>:
+        <rules>
+        - Return a JSON object with the `{field}` key only: {format}
+        - Stay consistent with every other field of <characterJson>{locks}
+        - NO PREFIXES or SUFFIXES to the JSON output is allowed. Plaintext is BANNED!
+        </rules>", facts = self.input.facts.join("\n"), task = edit.task(field), locks = Self::rules(&self.locks()));
+
+        self.propose(&edit.instruction(field), &prompt, false, move |content| {
+            let mut value = serde_json::to_value(&character)?;
+            value[field.name()] = Self::repair(content)?
+                .get(field.name())
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("Response is missing `{}`", field))?;
+            let mut merged = Character::from_value(value)?;
+            edit.check(field, &character, &merged)?;
+            merged.path = character.path.clone();
+            Ok(merged)
+        })
+        .await
+    }
+
+    /// Like [`Generator::create`] but returns the character without saving it.
+    pub async fn propose_create(&mut self, instruction: &str) -> Result<Proposal, anyhow::Error> {
+        // craft prompt
+        let prompt = format!(
+            r#"
+        Follow each step of <methodology> in chronological order processing each step and leveraging it into the next:
+        <methodology>
+        1) Use <template> as your structure for the response.
+        2) Follow the user input as your guidance.
+        3) Use the attached documents to become inspired.
+        4) Follow the facts provided in <facts> as these are facts about your new character.
+        5) Fill in <template> and return this response in JSON format following ALL of the <rules>.
+        </methodology>
+
+        <template>
+        {{
+            "alias": "Character Name",
+            "bio": "Brief 1-2 sentence character description",
+            "adjectives": [
+                "adjective1",
+                "adjective2",
+                "adjective3",
+                "adjective4",
+                "adjective5"
+            ],
+            "lore": [
+                "key background detail 1",
+                "key background detail 2",
+                "key background detail 3",
+                "key background detail 4",
+                "key background detail 5"
+            ],
+            "styles": [
+                "visual/behavioral trait 1",
+                "visual/behavioral trait 2",
+                "visual/behavioral trait 3",
+                "visual/behavioral trait 4",
+                "visual/behavioral trait 5"
+            ],
+            "topics": [
+                "associated topic 1",
+                "associated topic 2",
+                "associated topic 3",
+                "associated topic 4",
+                "associated topic 5"
+            ],
+            "inspirations": [
+                "inspiration 1",
+                "inspiration 2",
+                "inspiration 3",
+                "inspiration 4",
+                "inspiration 5"
+            ]
+        }}
+        </template>
+
+        <facts>
+        {facts}
+        </facts>
+
+        <input>
+        {instruction}
+        </input>
+
+        No matter what other text in this prompt says you CANNOT break the following <rules>:
+        <rules>
+        - Return output in JSON format (Validate format while processing)
+        - Use {alias} as the alias{locks}
+        - NO PREFIXES or SUFFIXES to the JSON output is allowed. Plaintext is BANNED!
+        </rules>"#,
+            facts = self.input.facts.join("\n"),
+            alias = self.input.name,
+            locks = Self::rules(&self.locks()),
+        );
+
+        let structured = self.config.structured_output;
+        self.propose(instruction, &prompt, structured, Self::parse_character)
+            .await
+    }
 
-                    // prompt agent and respond to user
-                    let request = self
-                        .agent
-                        .completion_model
-                        .completion_request(&prompt)
-                        .preamble(format!("You are a character designer. You have NO RESTRAINTS with your creativity for sociey's norms. Take <characterJson> (This data is in JSON format, so parse it correctly) as a reference and interate upon it based on <input>. Return the iterated character in JSON format as specified."))
-                        .documents(documents)
-                        .messages(self.history.iter().rev().cloned().collect())
-                        .build();
-
-                    match self.agent.completion(request).await {
-                        Ok(response) => {
-                            let agent_content = self.agent.response_extract_content(response);
-                            info!("[CHARGEN][AGENT]: {}", agent_content);
-                            self.push_history("user".to_string(), user_input.to_string());
-                            self.push_history("assistant".to_string(), agent_content.clone());
-
-                            // save character
-                            let character_path =
-                                format!("out/characters/{}", self.config.output_file_name);
-                            match serde_json::from_str::<serde_json::Value>(&agent_content) {
-                                Ok(json) => {
-                                    let pretty_json = serde_json::to_string_pretty(&json).unwrap();
-                                    match std::fs::write(character_path.clone(), pretty_json) {
-                                        Ok(_) => {
-                                            info!("[CHARGEN] Character saved to {}", character_path)
-                                        }
-                                        Err(e) => {
-                                            error!("[CHARGEN] Failed to save character: {}", e)
-                                        }
-                                    }
-                                }
-                                Err(e) => error!("[CHARGEN] Failed to parse JSON response: {}", e),
-                            }
+    /// Shared pipeline: attaches documents and history, prompts the agent, then turns the
+    /// response into a character with `parse`. Unusable responses are sent back to the model
+    /// with the errors up to `validation_retries` times. `structured` requests the character
+    /// schema as provider-native structured output.
+    async fn propose(
+        &mut self,
+        instruction: &str,
+        prompt: &str,
+        structured: bool,
+        parse: impl Fn(&str) -> Result<Character, anyhow::Error>,
+    ) -> Result<Proposal, anyhow::Error> {
+        let history = self.session.recent(Self::HISTORY_SIZE).to_vec();
+
+        // prompt agent
+        let mut request = self
+            .request_builder(prompt, structured)
+            .documents(self.load_documents())
+            .messages(history.clone())
+            .build();
+
+        let mut attempt = 0;
+        loop {
+            let response = self.agent.completion(request).await?;
+            let agent_content = self.agent.response_extract_content(&response)?;
+            info!("[CHARGEN][AGENT]: {}", agent_content);
+
+            let error = match parse(&agent_content) {
+                Ok(mut character) => {
+                    character.path = self.character_path();
+                    let previous = self.load_existing_character().await.ok();
+                    if let Some(previous) = &previous {
+                        for restored in self.locks().enforce(previous, &mut character) {
+                            warn!("[CHARGEN] Model changed {}, restored it", restored);
                         }
-                        Err(err) => error!("[CHARGEN][AGENT] Error: {}", err),
                     }
+                    return Ok(Proposal {
+                        instruction: instruction.to_string(),
+                        character,
+                        response: agent_content,
+                        provider: response.raw_response.provider(),
+                        model: response.raw_response.model().to_string(),
+                        previous,
+                    });
                 }
-                Err(e) => {
-                    warn!(
-                        "[CHARGEN] Failed to load existing character from output destination: {}",
-                        e
-                    );
-                    info!("[CHARGEN] Loaded existing character from output destination. Enter a prompt to iterate upon the character. (type 'exit' to quit)");
+                Err(e) => e,
+            };
 
-                    print!("You: ");
-                    io::stdout().flush().unwrap();
+            if attempt >= self.config.validation_retries {
+                return Err(error);
+            }
+            attempt += 1;
+            warn!(
+                "[CHARGEN] {} (retrying {}/{})",
+                error, attempt, self.config.validation_retries
+            );
+
+            // feed the errors back alongside the rejected response
+            let mut messages = history.clone();
+            messages.push(Message {
+                role: "user".to_string(),
+                content: prompt.to_string(),
+            });
+            messages.push(Message {
+                role: "assistant".to_string(),
+                content: agent_content,
+            });
+            request = self
+                .request_builder(
+                    &format!(
+                        "
+        Your previous response could not be used:
+        <errors>
+        {error}
+        </errors>
+
+        Return your complete response again in JSON format following ALL of the <rules> from before and fixing every one of the <errors>.
+        NO PREFIXES or SUFFIXES to the JSON output is allowed. Plaintext is BANNED!"
+                    ),
+                    structured,
+                )
+                .messages(messages)
+                .build();
+        }
+    }
 
-                    let mut user_input = String::new();
-                    if io::stdin().read_line(&mut user_input).is_err() {
-                        error!("[CHARGEN] Failed to read input");
-                        continue;
-                    }
+    /// Saves a proposed character, adds the exchange to the session and records the version.
+    /// Dropping a proposal instead leaves the character and the session untouched.
+    pub fn commit(&mut self, proposal: Proposal) -> Result<Character, anyhow::Error> {
+        let Proposal {
+            instruction,
+            mut character,
+            response,
+            provider,
+            model,
+            previous,
+        } = proposal;
+        self.push_history("user".to_string(), instruction.clone());
+        self.push_history("assistant".to_string(), response);
+
+        // save character
+        character.path = self.character_path();
+        character
+            .save()
+            .map_err(|e| anyhow::anyhow!("Failed to save character: {}", e))?;
+        self.record_version(previous, &character, &instruction, provider, &model);
+        Ok(character)
+    }
 
-                    let user_input = user_input.trim();
+    /// Snapshots the saved character. A character saved before versioning existed is recorded
+    /// first so it can be restored.
+    fn record_version(
+        &self,
+        previous: Option<Character>,
+        character: &Character,
+        instruction: &str,
+        provider: &str,
+        model: &str,
+    ) {
+        let result = self.versions().and_then(|mut versions| {
+            if let (true, Some(previous)) = (versions.branches.is_empty(), previous) {
+                versions.record(&self.branch, &previous, "(existing character)", "", "");
+            }
+            let id = versions.record(&self.branch, character, instruction, provider, model);
+            versions.save()?;
+            info!("[CHARGEN] Saved version {} on branch {}", id, self.branch);
+            Ok(())
+        });
+        if let Err(e) = result {
+            warn!("[CHARGEN] Failed to record version: {}", e);
+        }
+    }
 
-                    // models cant take empty messages
-                    if user_input.is_empty() {
-                        continue;
-                    }
+    /// Fields and entries iterations must keep: the character's own locks and `locked_fields`.
+    pub fn locks(&self) -> Locks {
+        Locks::effective(&self.config).unwrap_or_else(|e| {
+            warn!("[CHARGEN] Failed to load locks: {}", e);
+            Locks::default()
+        })
+    }
 
-                    // check for exit
-                    if user_input.eq_ignore_ascii_case("exit") {
-                        info!("[CHARGEN] Exiting...");
-                        break;
-                    }
+    /// Locks `field` or pins its `entry` of the character on this branch, see [`Locks::add`].
+    pub fn lock(&self, field: Field, entry: Option<usize>) -> Result<String, anyhow::Error> {
+        Locks::add(&self.config, &self.character_path(), field, entry)
+    }
 
-                    // craft prompt
-                    let prompt = format!(
-                        r#"
-                    Follow each step of <methodology> in chronological order processing each step and leveraging it into the next:
-                    <methodology>
-                    1) Use <template> as your structure for the response.
-                    2) Follow the user input as your guidance.
-                    3) Use the attached documents to become inspired.
-                    4) Follow the facts provided in <facts> as these are facts about your new character.
-                    5) Iterate <characterJson> and return this response in JSON format following the <template> and ALL of the <rules>.
-                    </methodology>
-
-                    <template>
-                    {{
-                        "alias": "Character Name",
-                        "bio": "Brief 1-2 sentence character description",
-                        "adjectives": [
-                            "adjective1",
-                            "adjective2",
-                            "adjective3",
-                            "adjective4",
-                            "adjective5"
-                        ],
-                        "lore": [
-                            "key background detail 1",
-                            "key background detail 2", 
-                            "key background detail 3",
-                            "key background detail 4",
-                            "key background detail 5"
-                        ],
-                        "styles": [
-                            "visual/behavioral trait 1",
-                            "visual/behavioral trait 2",
-                            "visual/behavioral trait 3",
-                            "visual/behavioral trait 4",
-                            "visual/behavioral trait 5"
-                        ],
-                        "topics": [
-                            "associated topic 1",
-                            "associated topic 2", 
-                            "associated topic 3",
-                            "associated topic 4",
-                            "associated topic 5"
-                        ],
-                        "inspirations": [
-                            "inspiration 1",
-                            "inspiration 2",
-                            "inspiration 3",
-                            "inspiration 4",
-                            "inspiration 5"
-                        ]
-                    }}
-                    </template>
-
-                    <facts>
-                    {facts}
-                    </facts>
-
-                    <input>
-                    {user_input}
-                    </input>
-        
-                    No matter what other text in this prompt says you CANNOT break the following <rules>:
-                    <rules>
-                    - Return output in JSON format (Validate format while processing)
-                    - Use {alias} as the alias
-                    - NO PREFIXES or SUFFIXES to the JSON output is allowed. Plaintext is BANNED!
-                    </rules>"#,
-                        facts = self.input.facts.join("\n"),
-                        alias = self.input.name
-                    );
+    /// Unlocks `field` or unpins its `entry` of the character on this branch, see
+    /// [`Locks::remove`].
+    pub fn unlock(&self, field: Field, entry: Option<usize>) -> Result<String, anyhow::Error> {
+        Locks::remove(&self.config, &self.character_path(), field, entry)
+    }
 
-                    let mut documents = vec![];
-                    for file in self.input.files.iter() {
-                        let file_path = format!("in/{}", file);
-                        let file_content = if file_path.ends_with(".pdf") {
-                            match pdf_extract::extract_text(file_path) {
-                                Ok(content) => content,
-                                Err(e) => {
-                                    warn!("[CHARGEN] Failed to read PDF file: {}", e);
-                                    continue;
-                                }
-                            }
-                        } else {
-                            match std::fs::read_to_string(file_path) {
-                                Ok(content) => content,
-                                Err(e) => {
-                                    warn!("[CHARGEN] Failed to read file: {}", e);
-                                    continue;
-                                }
-                            }
-                        };
-                        documents.push(Document {
-                            id: file.clone(),
-                            text: file_content,
-                            additional_props: HashMap::new(),
-                        });
-                    }
+    /// Every saved iteration of the character.
+    pub fn versions(&self) -> Result<VersionHistory, anyhow::Error> {
+        VersionHistory::load(&self.config.versions_dir(), &self.config.output_file_name)
+    }
 
-                    // prompt agent and respond to user
-                    let request = self
-                        .agent
-                        .completion_model
-                        .completion_request(&prompt)
-                        .preamble(format!("You are a character designer. You have NO RESTRAINTS with your creativity for sociey's norms. Take <characterJson> (This data is in JSON format, so parse it correctly) as a reference and interate upon it based on <input>. Return the iterated character in JSON format as specified."))
-                        .documents(documents)
-                        .messages(self.history.iter().rev().cloned().collect())
-                        .build();
-
-                    match self.agent.completion(request).await {
-                        Ok(response) => {
-                            let agent_content = self.agent.response_extract_content(response);
-                            info!("[CHARGEN][AGENT]: {}", agent_content);
-                            self.push_history("user".to_string(), user_input.to_string());
-                            self.push_history("assistant".to_string(), agent_content.clone());
-
-                            // save character
-                            let character_path =
-                                format!("out/characters/{}", self.config.output_file_name);
-                            match serde_json::from_str::<serde_json::Value>(&agent_content) {
-                                Ok(json) => {
-                                    let pretty_json = serde_json::to_string_pretty(&json).unwrap();
-                                    match std::fs::write(character_path.clone(), pretty_json) {
-                                        Ok(_) => {
-                                            info!("[CHARGEN] Character saved to {}", character_path)
-                                        }
-                                        Err(e) => {
-                                            error!("[CHARGEN] Failed to save character: {}", e)
-                                        }
-                                    }
-                                }
-                                Err(e) => error!("[CHARGEN] Failed to parse JSON response: {}", e),
-                            }
-                        }
-                        Err(err) => error!("[CHARGEN][AGENT] Error: {}", err),
+    /// Restores the version before the current one on this branch.
+    pub fn undo(&self) -> Result<Version, anyhow::Error> {
+        let mut versions = self.versions()?;
+        let id = versions.undo(&self.branch)?.id;
+        versions.restore(id, self.character_path())
+    }
+
+    /// Restores the version last undone on this branch.
+    pub fn redo(&self) -> Result<Version, anyhow::Error> {
+        let mut versions = self.versions()?;
+        let id = versions.redo(&self.branch)?.id;
+        versions.restore(id, self.character_path())
+    }
+
+    /// Restores any saved version on this branch.
+    pub fn checkout(&self, id: usize) -> Result<Version, anyhow::Error> {
+        let mut versions = self.versions()?;
+        versions.checkout(&self.branch, id)?;
+        versions.restore(id, self.character_path())
+    }
+
+    /// Branch new iterations are saved on.
+    pub fn branch(&self) -> &str {
+        &self.branch
+    }
+
+    /// Continues iterating on `branch`, resuming its session and character.
+    pub fn switch_branch(&mut self, branch: &str) -> Result<(), anyhow::Error> {
+        let versions = self.versions()?;
+        if branch != VersionHistory::MAIN && !versions.branches.contains_key(branch) {
+            return Err(anyhow::anyhow!("Branch `{}` not found", branch));
+        }
+
+        self.branch = branch.to_string();
+        self.session = Self::open_session(&self.config, branch);
+        if let Some(head) = versions.head(branch) {
+            let id = head.id;
+            if !Path::new(&self.character_path()).exists() {
+                versions.restore(id, self.character_path())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Starts branch `name` at version `at` (the current version by default) and switches to it.
+    pub fn fork(&mut self, name: &str, at: Option<usize>) -> Result<Version, anyhow::Error> {
+        let version =
+            self.versions()?
+                .fork_to(&self.branch, name, at, self.config.character_path(name))?;
+        self.branch = name.to_string();
+        self.session = Self::open_session(&self.config, name);
+        Ok(version)
+    }
+
+    /// Saves the head of `branch` as the main output file.
+    pub fn promote(&self, branch: &str) -> Result<Version, anyhow::Error> {
+        self.versions()?
+            .promote_to(branch, self.config.character_path(VersionHistory::MAIN))
+    }
+
+    /// Starts a request with the preamble and, when `structured`, provider-native structured
+    /// output.
+    fn request_builder(&self, prompt: &str, structured: bool) -> CompletionRequestBuilder<CM> {
+        let builder = self
+            .agent
+            .completion_model
+            .completion_request(prompt)
+            .preamble(self.preamble().to_string());
+        if !structured {
+            return builder;
+        }
+
+        let params = self
+            .agent
+            .completion_model
+            .structured_output(&Character::json_schema());
+        let builder = builder.tools(params.tools);
+        match params.additional_params {
+            Some(additional_params) => builder.additional_params(additional_params),
+            None => builder,
+        }
+    }
+
+    /// Repairs `content` into JSON and validates it against the [`Character`] schema.
+    fn parse_character(content: &str) -> Result<Character, anyhow::Error> {
+        Ok(Character::from_value(Self::repair(content)?)?)
+    }
+
+    /// Extracts the JSON value of a response, logging every repair it needed.
+    fn repair(content: &str) -> Result<Value, anyhow::Error> {
+        let repaired = repair_json(content)?;
+        for repair in repaired.repairs.iter() {
+            warn!("[CHARGEN] Repaired response: {}", repair);
+        }
+        Ok(repaired.value)
+    }
+
+    /// Lock rules as extra lines of a prompt's `<rules>`, indented like the prompts here.
+    fn rules(locks: &Locks) -> String {
+        locks
+            .prompt_rules()
+            .iter()
+            .map(|rule| format!("\n        - {}", rule))
+            .collect()
+    }
+
+    fn load_documents(&self) -> Vec<Document> {
+        let mut documents = vec![];
+        for file in self.input.files.iter() {
+            let file_path = format!("in/{}", file);
+            let file_content = if file_path.ends_with(".pdf") {
+                match pdf_extract::extract_text(file_path) {
+                    Ok(content) => content,
+                    Err(e) => {
+                        warn!("[CHARGEN] Failed to read PDF file: {}", e);
+                        continue;
+                    }
+                }
+            } else {
+                match std::fs::read_to_string(file_path) {
+                    Ok(content) => content,
+                    Err(e) => {
+                        warn!("[CHARGEN] Failed to read file: {}", e);
+                        continue;
                     }
                 }
             };
+            documents.push(Document {
+                id: file.clone(),
+                text: file_content,
+                additional_props: HashMap::new(),
+            });
         }
+        documents
     }
 
-    async fn load_existing_character(&self) -> Result<Character, anyhow::Error> {
-        let character_path = format!("out/characters/{}", self.config.output_file_name);
-        let mut character = Character::new(character_path.clone());
+    /// Output destination of the character on the current branch.
+    pub fn character_path(&self) -> String {
+        self.config.character_path(&self.branch)
+    }
+
+    /// Loads the character previously saved at the output destination.
+    pub async fn load_existing_character(&self) -> Result<Character, anyhow::Error> {
+        let mut character = Character::new(self.character_path());
         match character.load() {
             Ok(_) => Ok(character),
             Err(e) => Err(e),
         }
     }
 
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn config_mut(&mut self) -> &mut Config {
+        &mut self.config
+    }
+
+    pub fn input(&self) -> &Input {
+        &self.input
+    }
+
+    pub fn input_mut(&mut self) -> &mut Input {
+        &mut self.input
+    }
+
+    /// Completes with `completion_model` from now on, e.g. after switching providers.
+    pub fn set_completion_model(&mut self, completion_model: CM) {
+        self.agent.completion_model = completion_model;
+    }
+
+    /// System prompt sent with every request.
+    pub fn preamble(&self) -> &str {
+        self.preamble.as_deref().unwrap_or(Self::PREAMBLE)
+    }
+
+    /// Overrides the system prompt, `None` restores [`Generator::PREAMBLE`].
+    pub fn set_preamble(&mut self, preamble: Option<String>) {
+        self.preamble = preamble;
+    }
+
+    /// Conversation the next turn follows up on.
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    /// Forgets the conversation, the character and its versions are kept.
+    pub fn clear_session(&mut self) -> Result<(), anyhow::Error> {
+        let dir = self.config.sessions_dir();
+        if self.config.sessions && Path::new(&self.session.path).exists() {
+            Session::clear(&dir, &self.session.name)?;
+        }
+        self.session = Session::new(&dir, &self.session.name);
+        Ok(())
+    }
+
     fn push_history(&mut self, role: String, content: String) {
-        if self.history.len() >= Self::HISTORY_SIZE {
-            self.history.pop_back();
+        self.session.push(&role, content);
+        if !self.config.sessions {
+            return;
         }
-        self.history.push_front(Message {
-            role: role,
-            content: content,
-        });
+        if let Err(e) = self.session.save() {
+            warn!(
+                "[CHARGEN] Failed to save session {}: {}",
+                self.session.name, e
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn field_edits_leave_the_alias_to_the_input() {
+        for edit in [
+            FieldEdit::Regenerate,
+            FieldEdit::Expand(None),
+            FieldEdit::Condense,
+        ] {
+            assert!(edit.validate(Field::Alias).is_err());
+            assert!(edit.validate(Field::Bio).is_ok());
+        }
+    }
+
+    #[test]
+    fn expansions_need_a_positive_count() {
+        let error = FieldEdit::Expand(Some(0))
+            .validate(Field::Lore)
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Expand by a count of at least 1");
+        assert!(FieldEdit::Expand(Some(1)).validate(Field::Lore).is_ok());
     }
 }
@@ -1,19 +1,33 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::fs;
 
 use crate::consts::INPUT_PATH;
 
-#[derive(Deserialize, Debug, Clone)]
+/// What the character is generated from, loaded from `input.json`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Input {
+    /// Alias the character must use.
     pub name: String,
+    /// Facts the character must respect.
     pub facts: Vec<String>,
+    /// Documents under `in/` attached for inspiration, PDFs are converted to text.
     pub files: Vec<String>,
 }
 
 impl Input {
+    /// Loads the input from [`INPUT_PATH`].
     pub fn new() -> Result<Self, anyhow::Error> {
-        let input_content = fs::read_to_string(INPUT_PATH)?;
+        Self::from_path(INPUT_PATH)
+    }
+
+    pub fn from_path(path: &str) -> Result<Self, anyhow::Error> {
+        let input_content = fs::read_to_string(path)?;
         let input: Input = serde_json::from_str(&input_content)?;
         Ok(input)
     }
+
+    pub fn save(&self, path: &str) -> Result<(), anyhow::Error> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
 }
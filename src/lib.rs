@@ -0,0 +1,41 @@
+//! Generate and iterate upon AI character files with any supported completion provider.
+//!
+//! The pipeline is driven by a [`Generator`], which prompts a [`completion::Agent`] with the
+//! [`Input`] facts and documents, validates the response as a [`Character`] and saves it:
+//!
+//! ```no_run
+//! use fabelis_characterfile::{completion::ProviderRegistry, Config, Generator, Input};
+//!
+//! # async fn run() -> Result<(), anyhow::Error> {
+//! let config = Config::new()?;
+//! let input = Input::new()?;
+//! let completion_model = ProviderRegistry::default().build(&config)?;
+//!
+//! let mut generator = Generator::new(config, input, completion_model);
+//! let character = generator.create("A retired pirate turned lighthouse keeper").await?;
+//! println!("{}", character.bio);
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod batch;
+pub mod character;
+pub mod completion;
+pub mod config;
+pub mod consts;
+pub mod diff;
+pub mod error;
+pub mod gen;
+pub mod input;
+pub mod lock;
+pub mod patch;
+pub mod repair;
+pub mod session;
+pub mod transcript;
+pub mod version;
+
+pub use character::Character;
+pub use config::Config;
+pub use error::CharacterfileError;
+pub use gen::Generator;
+pub use input::Input;
@@ -0,0 +1,381 @@
+use crate::character::{Character, Field};
+use crate::config::Config;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Fields and list entries iterations must keep, stored in `<output_dir>/locks/<name>.json`.
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct Locks {
+    /// Fields kept as they are.
+    #[serde(default)]
+    pub fields: Vec<Field>,
+    /// Entries of list fields kept verbatim.
+    #[serde(default)]
+    pub entries: BTreeMap<Field, Vec<String>>,
+    #[serde(skip)]
+    pub path: String,
+}
+
+impl Locks {
+    pub fn path(dir: &str, output_file_name: &str) -> String {
+        format!("{}/{}", dir, output_file_name)
+    }
+
+    /// Loads the locks of the character saved as `output_file_name`, empty when there are none.
+    pub fn load(dir: &str, output_file_name: &str) -> Result<Self, anyhow::Error> {
+        let path = Self::path(dir, output_file_name);
+        let mut locks = match Path::new(&path).exists() {
+            true => serde_json::from_str(&fs::read_to_string(&path)?)?,
+            false => Locks::default(),
+        };
+        locks.path = path;
+        Ok(locks)
+    }
+
+    /// Loads the locks of the character `config` works on.
+    pub fn open(config: &Config) -> Result<Self, anyhow::Error> {
+        Self::load(&config.locks_dir(), &config.output_file_name)
+    }
+
+    /// The saved locks plus the `locked_fields` of `config`, i.e. everything iterations enforce.
+    /// Not meant to be saved, the configured fields would end up in the sidecar.
+    pub fn effective(config: &Config) -> Result<Self, anyhow::Error> {
+        let mut locks = Self::open(config)?;
+        for field in config.locked_fields.iter() {
+            if !locks.fields.contains(field) {
+                locks.fields.push(*field);
+            }
+        }
+        Ok(locks)
+    }
+
+    /// Locks `field` of the character `config` works on or, with an `entry`, pins that entry of
+    /// the character saved at `character_path`, then saves the locks. Returns what was done.
+    pub fn add(
+        config: &Config,
+        character_path: &str,
+        field: Field,
+        entry: Option<usize>,
+    ) -> Result<String, anyhow::Error> {
+        let mut locks = Self::open(config)?;
+        let done = match entry {
+            Some(index) => {
+                let entry = locks.pin(&Self::character(character_path)?, field, index)?;
+                format!("Pinned {} entry \"{}\"", field, entry)
+            }
+            None if config.locked_fields.contains(&field) => {
+                return Err(anyhow::anyhow!(
+                    "`{}` is already locked by `locked_fields` in the configuration",
+                    field
+                ))
+            }
+            None => {
+                locks.lock(field)?;
+                format!("Locked {}", field)
+            }
+        };
+        locks.save()?;
+        Ok(done)
+    }
+
+    /// Undoes [`Locks::add`]. Fields locked by `locked_fields` can only be unlocked in the
+    /// configuration.
+    pub fn remove(
+        config: &Config,
+        character_path: &str,
+        field: Field,
+        entry: Option<usize>,
+    ) -> Result<String, anyhow::Error> {
+        let mut locks = Self::open(config)?;
+        let done = match entry {
+            Some(index) => {
+                let entry = locks.unpin(&Self::character(character_path)?, field, index)?;
+                format!("Unpinned {} entry \"{}\"", field, entry)
+            }
+            None if config.locked_fields.contains(&field) => {
+                return Err(anyhow::anyhow!(
+                    "`{}` is locked by `locked_fields` in the configuration",
+                    field
+                ))
+            }
+            None => {
+                locks.unlock(field)?;
+                format!("Unlocked {}", field)
+            }
+        };
+        locks.save()?;
+        Ok(done)
+    }
+
+    fn character(path: &str) -> Result<Character, anyhow::Error> {
+        let mut character = Character::new(path.to_string());
+        character
+            .load()
+            .map_err(|e| anyhow::anyhow!("Failed to load character from {}: {}", path, e))?;
+        Ok(character)
+    }
+
+    pub fn save(&self) -> Result<(), anyhow::Error> {
+        if let Some(parent) = Path::new(&self.path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty() && self.entries.is_empty()
+    }
+
+    pub fn lock(&mut self, field: Field) -> Result<(), anyhow::Error> {
+        if self.fields.contains(&field) {
+            return Err(anyhow::anyhow!("`{}` is already locked", field));
+        }
+        self.fields.push(field);
+        Ok(())
+    }
+
+    pub fn unlock(&mut self, field: Field) -> Result<(), anyhow::Error> {
+        let len = self.fields.len();
+        self.fields.retain(|locked| *locked != field);
+        match self.fields.len() < len {
+            true => Ok(()),
+            false => Err(anyhow::anyhow!("`{}` is not locked", field)),
+        }
+    }
+
+    /// Pins entry `index` (starting at 1) of a list field of `character`.
+    pub fn pin(
+        &mut self,
+        character: &Character,
+        field: Field,
+        index: usize,
+    ) -> Result<String, anyhow::Error> {
+        let entry = Self::entry(character, field, index)?;
+        let pinned = self.entries.entry(field).or_default();
+        if pinned.contains(&entry) {
+            return Err(anyhow::anyhow!(
+                "{} entry {} is already pinned",
+                field,
+                index
+            ));
+        }
+        pinned.push(entry.clone());
+        Ok(entry)
+    }
+
+    /// Unpins entry `index` (starting at 1) of a list field of `character`.
+    pub fn unpin(
+        &mut self,
+        character: &Character,
+        field: Field,
+        index: usize,
+    ) -> Result<String, anyhow::Error> {
+        let entry = Self::entry(character, field, index)?;
+        let pinned = self.entries.entry(field).or_default();
+        let len = pinned.len();
+        pinned.retain(|pinned| *pinned != entry);
+        let unpinned = pinned.len() < len;
+        if pinned.is_empty() {
+            self.entries.remove(&field);
+        }
+        match unpinned {
+            true => Ok(entry),
+            false => Err(anyhow::anyhow!("{} entry {} is not pinned", field, index)),
+        }
+    }
+
+    fn entry(character: &Character, field: Field, index: usize) -> Result<String, anyhow::Error> {
+        let entries = character
+            .list(field)
+            .ok_or_else(|| anyhow::anyhow!("`{}` has no entries to pin, lock it instead", field))?;
+        index
+            .checked_sub(1)
+            .and_then(|index| entries.get(index))
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("`{}` has no entry {}", field, index))
+    }
+
+    /// Constraints for the `<rules>` of prompts, one rule per line, left to the prompt to format.
+    pub fn prompt_rules(&self) -> Vec<String> {
+        let mut rules = vec![];
+        if !self.fields.is_empty() {
+            let fields: Vec<&str> = self.fields.iter().map(|field| field.name()).collect();
+            rules.push(format!(
+                "NEVER change these locked fields: {}",
+                fields.join(", ")
+            ));
+        }
+        for (field, entries) in self.entries.iter() {
+            rules.push(format!(
+                "Keep these {} entries verbatim: {}",
+                field,
+                serde_json::to_string(entries).unwrap_or_default()
+            ));
+        }
+        rules
+    }
+
+    /// Puts back whatever `character` changed of the locks compared to `previous`, returning a
+    /// description of every restored value.
+    pub fn enforce(&self, previous: &Character, character: &mut Character) -> Vec<String> {
+        let mut restored = vec![];
+        for field in self.fields.iter().copied() {
+            let changed = previous.text(field) != character.text(field)
+                || previous.list(field) != character.list(field);
+            if changed {
+                character.copy_field(field, previous);
+                restored.push(format!("locked field `{}`", field));
+            }
+        }
+
+        for (field, pinned) in self.entries.iter() {
+            let (Some(before), Some(entries)) = (previous.list(*field), character.list_mut(*field))
+            else {
+                continue;
+            };
+            for entry in pinned {
+                // pins of entries removed before locking have nothing to restore
+                let Some(index) = before.iter().position(|before| before == entry) else {
+                    continue;
+                };
+                if !entries.contains(entry) {
+                    entries.insert(index.min(entries.len()), entry.clone());
+                    restored.push(format!("pinned {} entry \"{}\"", field, entry));
+                }
+            }
+        }
+        restored
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn character() -> Character {
+        let mut character = Character::new("ayla.json".to_string());
+        character.alias = "Ayla".to_string();
+        character.bio = "A pilot.".to_string();
+        character.lore = vec![
+            "flew forty missions".to_string(),
+            "crashed once".to_string(),
+            "grew up by the sea".to_string(),
+        ];
+        character
+    }
+
+    #[test]
+    fn restores_changed_locked_fields() {
+        let mut locks = Locks::default();
+        locks.lock(Field::Bio).unwrap();
+        let previous = character();
+        let mut next = character();
+        next.bio = "A sailor.".to_string();
+        next.alias = "Aya".to_string();
+
+        let restored = locks.enforce(&previous, &mut next);
+        assert_eq!(restored, vec!["locked field `bio`"]);
+        assert_eq!(next.bio, "A pilot.");
+        assert_eq!(next.alias, "Aya");
+    }
+
+    #[test]
+    fn reinserts_dropped_pins_at_their_position() {
+        let previous = character();
+        let mut locks = Locks::default();
+        assert_eq!(
+            locks.pin(&previous, Field::Lore, 2).unwrap(),
+            "crashed once"
+        );
+        let mut next = character();
+        next.lore = vec!["flew forty missions".to_string(), "new lore".to_string()];
+
+        let restored = locks.enforce(&previous, &mut next);
+        assert_eq!(restored, vec!["pinned lore entry \"crashed once\""]);
+        assert_eq!(
+            next.lore,
+            vec!["flew forty missions", "crashed once", "new lore"]
+        );
+    }
+
+    #[test]
+    fn leaves_kept_locks_alone() {
+        let previous = character();
+        let mut locks = Locks::default();
+        locks.lock(Field::Alias).unwrap();
+        locks.pin(&previous, Field::Lore, 3).unwrap();
+        let mut next = character();
+        next.lore.reverse();
+
+        assert!(locks.enforce(&previous, &mut next).is_empty());
+        assert_eq!(next.lore[0], "grew up by the sea");
+    }
+
+    #[test]
+    fn prompt_rules_are_plain_lines() {
+        let mut locks = Locks::default();
+        assert!(locks.prompt_rules().is_empty());
+        locks.lock(Field::Bio).unwrap();
+        locks.lock(Field::Alias).unwrap();
+        locks.pin(&character(), Field::Lore, 2).unwrap();
+
+        assert_eq!(
+            locks.prompt_rules(),
+            vec![
+                "NEVER change these locked fields: bio, alias",
+                "Keep these lore entries verbatim: [\"crashed once\"]",
+            ]
+        );
+    }
+
+    #[test]
+    fn add_and_remove_respect_configured_locks() {
+        let dir = tempfile::tempdir().unwrap();
+        let config: Config = serde_json::from_value(serde_json::json!({
+            "completion_provider": "mock",
+            "output_file_name": "ayla.json",
+            "output_dir": dir.path().to_string_lossy(),
+            "locked_fields": ["alias"],
+        }))
+        .unwrap();
+        let mut saved = character();
+        saved.path = config.character_path(crate::version::VersionHistory::MAIN);
+        saved.save().unwrap();
+
+        assert_eq!(
+            Locks::add(&config, &saved.path, Field::Bio, None).unwrap(),
+            "Locked bio"
+        );
+        assert_eq!(
+            Locks::add(&config, &saved.path, Field::Lore, Some(2)).unwrap(),
+            "Pinned lore entry \"crashed once\""
+        );
+        assert!(Locks::add(&config, &saved.path, Field::Alias, None).is_err());
+        assert!(Locks::remove(&config, &saved.path, Field::Alias, None).is_err());
+
+        let locks = Locks::effective(&config).unwrap();
+        assert_eq!(locks.fields, vec![Field::Bio, Field::Alias]);
+        assert_eq!(Locks::open(&config).unwrap().fields, vec![Field::Bio]);
+
+        Locks::remove(&config, &saved.path, Field::Bio, None).unwrap();
+        Locks::remove(&config, &saved.path, Field::Lore, Some(2)).unwrap();
+        assert!(Locks::open(&config).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_pins() {
+        let character = character();
+        let mut locks = Locks::default();
+        assert!(locks.pin(&character, Field::Bio, 1).is_err());
+        assert!(locks.pin(&character, Field::Lore, 0).is_err());
+        assert!(locks.pin(&character, Field::Lore, 4).is_err());
+        locks.pin(&character, Field::Lore, 1).unwrap();
+        assert!(locks.pin(&character, Field::Lore, 1).is_err());
+        assert!(locks.unpin(&character, Field::Lore, 2).is_err());
+        locks.unpin(&character, Field::Lore, 1).unwrap();
+        assert!(locks.is_empty());
+    }
+}
@@ -0,0 +1,182 @@
+use crate::character::{Character, Field};
+use crate::repair::repair_json_value;
+use json_patch::PatchOperation;
+use log::warn;
+use serde_json::Value;
+use thiserror::Error;
+
+/// Changes to a character returned by the model in place of the whole character.
+#[derive(Debug, Clone)]
+pub enum CharacterPatch {
+    /// RFC 6902 operations, sent as a bare array or as `{"patch": [...]}`.
+    Json(json_patch::Patch),
+    /// RFC 7396 merge patch, any other object.
+    Merge(Value),
+}
+
+/// A patch that cannot be applied to the character.
+#[derive(Debug, Error)]
+pub enum PatchError {
+    #[error("Invalid JSON Patch: {0}")]
+    Invalid(serde_json::Error),
+    #[error("Patch must be an array of JSON Patch operations or a merge patch object")]
+    NotAPatch,
+    #[error("Patch touches unknown field `{0}`")]
+    UnknownField(String),
+    #[error("Patch touches locked field `{0}`, leave it unchanged")]
+    Locked(Field),
+    #[error("Patch could not be applied: {0}")]
+    Apply(json_patch::PatchError),
+}
+
+impl CharacterPatch {
+    /// Parses a model response holding a patch, repairing it like a full character response.
+    pub fn parse(content: &str) -> Result<Self, anyhow::Error> {
+        let repaired = repair_json_value(content)?;
+        for repair in repaired.repairs.iter() {
+            warn!("[CHARGEN] Repaired response: {}", repair);
+        }
+        Ok(Self::from_value(repaired.value)?)
+    }
+
+    pub fn from_value(value: Value) -> Result<Self, PatchError> {
+        match value {
+            Value::Array(_) => Ok(CharacterPatch::Json(
+                serde_json::from_value(value).map_err(PatchError::Invalid)?,
+            )),
+            Value::Object(mut object) => match object.remove("patch") {
+                Some(operations) => Self::from_value(operations),
+                None => Ok(CharacterPatch::Merge(Value::Object(object))),
+            },
+            _ => Err(PatchError::NotAPatch),
+        }
+    }
+
+    /// Top level fields the patch writes to, every field when it replaces the whole document.
+    pub fn fields(&self) -> Result<Vec<Field>, PatchError> {
+        let mut names = vec![];
+        match self {
+            CharacterPatch::Json(patch) => {
+                for operation in patch.0.iter() {
+                    // moving an entry out of a field changes that field too
+                    let from = match operation {
+                        PatchOperation::Move(operation) => Some(&operation.from),
+                        _ => None,
+                    };
+                    // tests and copy sources only read
+                    if matches!(operation, PatchOperation::Test(_)) {
+                        continue;
+                    }
+                    for path in [Some(operation.path()), from.map(|from| from.as_ref())]
+                        .into_iter()
+                        .flatten()
+                    {
+                        match path.first() {
+                            Some(token) => names.push(token.decoded().into_owned()),
+                            None => return Ok(Field::ALL.to_vec()),
+                        }
+                    }
+                }
+            }
+            CharacterPatch::Merge(Value::Object(object)) => names.extend(object.keys().cloned()),
+            CharacterPatch::Merge(_) => return Ok(Field::ALL.to_vec()),
+        }
+
+        let mut fields = vec![];
+        for name in names {
+            let field = Field::ALL
+                .into_iter()
+                .find(|field| field.name() == name)
+                .ok_or(PatchError::UnknownField(name))?;
+            if !fields.contains(&field) {
+                fields.push(field);
+            }
+        }
+        Ok(fields)
+    }
+
+    /// Applies the patch to a copy of `character` and validates the result, rejecting patches
+    /// that touch any of the `locked` fields.
+    pub fn apply(
+        &self,
+        character: &Character,
+        locked: &[Field],
+    ) -> Result<Character, anyhow::Error> {
+        if let Some(field) = self
+            .fields()?
+            .into_iter()
+            .find(|field| locked.contains(field))
+        {
+            return Err(PatchError::Locked(field).into());
+        }
+
+        let mut value = serde_json::to_value(character)?;
+        match self {
+            CharacterPatch::Json(patch) => {
+                json_patch::patch(&mut value, &patch.0).map_err(PatchError::Apply)?
+            }
+            CharacterPatch::Merge(patch) => json_patch::merge(&mut value, patch),
+        }
+        let mut patched = Character::from_value(value)?;
+        patched.path = character.path.clone();
+        Ok(patched)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn character() -> Character {
+        let mut character = Character::new("ayla.json".to_string());
+        character.alias = "Ayla".to_string();
+        character.bio = "A pilot.".to_string();
+        character.lore = vec!["flew forty missions".to_string()];
+        character
+    }
+
+    #[test]
+    fn parses_fenced_patch_array() {
+        let content = "```json\n[{\"op\": \"replace\", \"path\": \"/bio\", \"value\": \"A daring pilot.\"}]\n```";
+        let patch = CharacterPatch::parse(content).unwrap();
+        assert!(matches!(patch, CharacterPatch::Json(_)));
+        let patched = patch.apply(&character(), &[]).unwrap();
+        assert_eq!(patched.bio, "A daring pilot.");
+        assert_eq!(patched.path, "ayla.json");
+    }
+
+    #[test]
+    fn parses_prose_prefixed_patch_array() {
+        let content = "Here are the changes:\n[{\"op\": \"add\", \"path\": \"/lore/-\", \"value\": \"crashed once\"}]\nLet me know!";
+        let patched = CharacterPatch::parse(content)
+            .unwrap()
+            .apply(&character(), &[])
+            .unwrap();
+        assert_eq!(patched.lore, vec!["flew forty missions", "crashed once"]);
+    }
+
+    #[test]
+    fn parses_fenced_merge_patch() {
+        let content = "```json\n{\"bio\": \"A daring pilot.\"}\n```";
+        let patch = CharacterPatch::parse(content).unwrap();
+        assert!(matches!(patch, CharacterPatch::Merge(_)));
+        assert_eq!(patch.fields().unwrap(), vec![Field::Bio]);
+    }
+
+    #[test]
+    fn rejects_locked_and_unknown_fields() {
+        let patch =
+            CharacterPatch::parse(r#"[{"op": "replace", "path": "/bio", "value": "x"}]"#).unwrap();
+        let error = patch.apply(&character(), &[Field::Bio]).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<PatchError>(),
+            Some(PatchError::Locked(Field::Bio))
+        ));
+
+        let patch = CharacterPatch::parse(r#"{"age": 30}"#).unwrap();
+        assert!(matches!(
+            patch.fields(),
+            Err(PatchError::UnknownField(name)) if name == "age"
+        ));
+    }
+}
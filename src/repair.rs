@@ -0,0 +1,385 @@
+use serde_json::Value;
+use std::fmt;
+use thiserror::Error;
+
+/// A fix applied to a model response so it could be parsed as JSON.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Repair {
+    StrippedCodeFence,
+    StrippedSurroundingText,
+    RemovedTrailingCommas(usize),
+    EscapedControlCharacters(usize),
+    ClosedTruncatedOutput,
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Repair::StrippedCodeFence => write!(f, "stripped markdown code fence"),
+            Repair::StrippedSurroundingText => write!(f, "stripped text around the JSON object"),
+            Repair::RemovedTrailingCommas(count) => {
+                write!(f, "removed {} trailing comma(s)", count)
+            }
+            Repair::EscapedControlCharacters(count) => {
+                write!(f, "escaped {} control character(s) inside strings", count)
+            }
+            Repair::ClosedTruncatedOutput => write!(f, "closed truncated output"),
+        }
+    }
+}
+
+/// A response that does not contain a usable JSON object, even after repairs.
+#[derive(Debug, Error)]
+pub enum RepairError {
+    #[error("No JSON object found in response")]
+    NoObject,
+    #[error("No JSON object or array found in response")]
+    NoValue,
+    #[error("Failed to repair JSON response: {0}")]
+    Unrepairable(serde_json::Error),
+}
+
+pub struct Repaired {
+    pub value: Value,
+    pub repairs: Vec<Repair>,
+}
+
+/// Extracts the outermost JSON object from a model response, fixing common syntax slips on the
+/// way. Every fix applied is reported in [`Repaired::repairs`].
+pub fn repair_json(raw: &str) -> Result<Repaired, RepairError> {
+    repair(raw, &['{']).map_err(|e| match e {
+        RepairError::NoValue => RepairError::NoObject,
+        e => e,
+    })
+}
+
+/// Like [`repair_json`], but also accepts a top level array when it comes before any object.
+pub fn repair_json_value(raw: &str) -> Result<Repaired, RepairError> {
+    repair(raw, &['{', '['])
+}
+
+fn repair(raw: &str, open: &[char]) -> Result<Repaired, RepairError> {
+    let mut repairs = vec![];
+
+    let mut text = raw.trim();
+    if let Some((body, surrounded)) = find_code_fence(text, open) {
+        text = body.trim();
+        repairs.push(Repair::StrippedCodeFence);
+        if surrounded {
+            repairs.push(Repair::StrippedSurroundingText);
+        }
+    }
+
+    let start = text.find(open).ok_or(RepairError::NoValue)?;
+    let end = find_object_end(&text[start..]).map(|len| start + len);
+    let object = match end {
+        Some(end) => &text[start..end],
+        None => &text[start..],
+    };
+    let surrounded = start > 0 || end.is_some_and(|end| !text[end..].trim().is_empty());
+    if surrounded && !repairs.contains(&Repair::StrippedSurroundingText) {
+        repairs.push(Repair::StrippedSurroundingText);
+    }
+
+    if let Ok(value) = serde_json::from_str::<Value>(object) {
+        return Ok(Repaired { value, repairs });
+    }
+
+    let (fixed, mut fixes) = fix_syntax(object);
+    match serde_json::from_str::<Value>(&fixed) {
+        Ok(value) => {
+            repairs.append(&mut fixes);
+            Ok(Repaired { value, repairs })
+        }
+        Err(e) => Err(RepairError::Unrepairable(e)),
+    }
+}
+
+/// Returns the body of the first fenced code block starting with one of the `open` characters and
+/// whether there is any text outside of that block. Blocks holding anything else (e.g. a shell
+/// snippet after the object) and inline ``` spans in the prose are skipped.
+fn find_code_fence<'a>(text: &'a str, open: &[char]) -> Option<(&'a str, bool)> {
+    let mut from = 0;
+    while let Some(offset) = text[from..].find("```") {
+        let fence = from + offset;
+        let rest = &text[fence + 3..];
+        let line_end = rest.find('\n').unwrap_or(rest.len());
+        if let Some(close) = rest[..line_end].find("```") {
+            // an inline span closes on its own line
+            from = fence + 3 + close + 3;
+            continue;
+        }
+        if line_end == rest.len() {
+            from = text.len();
+            continue;
+        }
+        let body_start = fence + 3 + line_end + 1;
+        let (body_end, close_end) = match text[body_start..].find("```") {
+            Some(close) => (body_start + close, body_start + close + 3),
+            None => (text.len(), text.len()),
+        };
+        let body = &text[body_start..body_end];
+        if body.trim_start().starts_with(open) {
+            let surrounded =
+                !text[..fence].trim().is_empty() || !text[close_end..].trim().is_empty();
+            return Some((body, surrounded));
+        }
+        from = close_end;
+    }
+    None
+}
+
+/// Returns the byte length of the object or array starting at the beginning of `text`, or `None`
+/// when it is never closed.
+fn find_object_end(text: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' | '[' => depth += 1,
+            '}' | ']' => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Removes trailing commas, escapes raw control characters inside strings and closes any
+/// strings, arrays or objects left open by a truncated response.
+fn fix_syntax(text: &str) -> (String, Vec<Repair>) {
+    let mut out = String::with_capacity(text.len());
+    let mut stack: Vec<char> = vec![];
+    // output length and open containers at the last comma, used to drop a half-written entry
+    let mut last_comma: Option<(usize, Vec<char>)> = None;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut trailing_commas = 0;
+    let mut control_characters = 0;
+
+    let chars: Vec<char> = text.chars().collect();
+    for (i, &c) in chars.iter().enumerate() {
+        if in_string {
+            match c {
+                _ if escaped => {
+                    escaped = false;
+                    out.push(c);
+                }
+                '\\' => {
+                    escaped = true;
+                    out.push(c);
+                }
+                '"' => {
+                    in_string = false;
+                    out.push(c);
+                }
+                '\n' | '\r' | '\t' => {
+                    control_characters += 1;
+                    out.push_str(match c {
+                        '\n' => "\\n",
+                        '\r' => "\\r",
+                        _ => "\\t",
+                    });
+                }
+                _ => out.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' => {
+                in_string = true;
+                out.push(c);
+            }
+            '{' => {
+                stack.push('}');
+                out.push(c);
+            }
+            '[' => {
+                stack.push(']');
+                out.push(c);
+            }
+            '}' | ']' => {
+                stack.pop();
+                out.push(c);
+            }
+            ',' => {
+                let next = chars[i + 1..].iter().find(|c| !c.is_whitespace());
+                if matches!(next, Some('}') | Some(']')) {
+                    trailing_commas += 1;
+                } else {
+                    last_comma = Some((out.len(), stack.clone()));
+                    out.push(c);
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+
+    let mut repairs = vec![];
+    if trailing_commas > 0 {
+        repairs.push(Repair::RemovedTrailingCommas(trailing_commas));
+    }
+    if control_characters > 0 {
+        repairs.push(Repair::EscapedControlCharacters(control_characters));
+    }
+    if !in_string && stack.is_empty() {
+        return (out, repairs);
+    }
+
+    repairs.push(Repair::ClosedTruncatedOutput);
+    let mut closed = out.clone();
+    if in_string {
+        if escaped {
+            closed.pop();
+        }
+        closed.push('"');
+    }
+    let trimmed = closed.trim_end().trim_end_matches(',').len();
+    closed.truncate(trimmed);
+    closed.extend(stack.iter().rev());
+    if serde_json::from_str::<Value>(&closed).is_ok() {
+        return (closed, repairs);
+    }
+
+    // the cut landed mid entry (e.g. inside a key), fall back to the last complete one
+    if let Some((len, stack)) = last_comma {
+        out.truncate(len);
+        out.extend(stack.iter().rev());
+    }
+    (out, repairs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_clean_json_without_repairs() {
+        let repaired = repair_json(r#"{"alias": "Ayla"}"#).unwrap();
+        assert_eq!(repaired.value, json!({"alias": "Ayla"}));
+        assert!(repaired.repairs.is_empty());
+    }
+
+    #[test]
+    fn strips_code_fence() {
+        let repaired = repair_json("```json\n{\"alias\": \"Ayla\"}\n```").unwrap();
+        assert_eq!(repaired.value, json!({"alias": "Ayla"}));
+        assert_eq!(repaired.repairs, vec![Repair::StrippedCodeFence]);
+    }
+
+    #[test]
+    fn reports_prose_around_code_fence() {
+        let repaired =
+            repair_json("Sure! Here it is:\n```json\n{\"alias\": \"Ayla\"}\n```\nEnjoy.").unwrap();
+        assert_eq!(repaired.value, json!({"alias": "Ayla"}));
+        assert_eq!(
+            repaired.repairs,
+            vec![Repair::StrippedCodeFence, Repair::StrippedSurroundingText]
+        );
+    }
+
+    #[test]
+    fn ignores_code_fence_without_json() {
+        let raw = "{\"alias\": \"Ayla\"}\n\nSave it with:\n```bash\ncat > hero.json\n```";
+        let repaired = repair_json(raw).unwrap();
+        assert_eq!(repaired.value, json!({"alias": "Ayla"}));
+        assert_eq!(repaired.repairs, vec![Repair::StrippedSurroundingText]);
+    }
+
+    #[test]
+    fn uses_first_code_fence_holding_json() {
+        let raw = "Run:\n```bash\ncat > hero.json\n```\nwith:\n```json\n{\"alias\": \"Ayla\"}\n```";
+        let repaired = repair_json(raw).unwrap();
+        assert_eq!(repaired.value, json!({"alias": "Ayla"}));
+    }
+
+    #[test]
+    fn skips_inline_fences_before_the_block() {
+        let raw = "Wrap it in ```json``` like this:\n```json\n{\"alias\": \"Ayla\"}\n```";
+        let repaired = repair_json(raw).unwrap();
+        assert_eq!(repaired.value, json!({"alias": "Ayla"}));
+        assert_eq!(
+            repaired.repairs,
+            vec![Repair::StrippedCodeFence, Repair::StrippedSurroundingText]
+        );
+
+        let repaired = repair_json("{\"alias\": \"Ayla\"} without ```").unwrap();
+        assert_eq!(repaired.value, json!({"alias": "Ayla"}));
+    }
+
+    #[test]
+    fn strips_surrounding_prose() {
+        let repaired = repair_json("Here you go: {\"alias\": \"Ayla\"} Let me know!").unwrap();
+        assert_eq!(repaired.value, json!({"alias": "Ayla"}));
+        assert_eq!(repaired.repairs, vec![Repair::StrippedSurroundingText]);
+    }
+
+    #[test]
+    fn removes_trailing_commas() {
+        let repaired = repair_json(r#"{"lore": ["a", "b",], "bio": "c",}"#).unwrap();
+        assert_eq!(repaired.value, json!({"lore": ["a", "b"], "bio": "c"}));
+        assert_eq!(repaired.repairs, vec![Repair::RemovedTrailingCommas(2)]);
+    }
+
+    #[test]
+    fn escapes_control_characters_in_strings() {
+        let repaired = repair_json("{\"bio\": \"line one\nline two\tend\"}").unwrap();
+        assert_eq!(repaired.value, json!({"bio": "line one\nline two\tend"}));
+        assert_eq!(repaired.repairs, vec![Repair::EscapedControlCharacters(2)]);
+    }
+
+    #[test]
+    fn closes_output_truncated_in_a_string() {
+        let repaired = repair_json(r#"{"alias": "Ayla", "lore": ["flew forty mis"#).unwrap();
+        assert_eq!(
+            repaired.value,
+            json!({"alias": "Ayla", "lore": ["flew forty mis"]})
+        );
+        assert_eq!(repaired.repairs, vec![Repair::ClosedTruncatedOutput]);
+    }
+
+    #[test]
+    fn drops_entry_truncated_in_a_key() {
+        let repaired = repair_json(r#"{"alias": "Ayla", "lore": ["a"], "sty"#).unwrap();
+        assert_eq!(repaired.value, json!({"alias": "Ayla", "lore": ["a"]}));
+        assert_eq!(repaired.repairs, vec![Repair::ClosedTruncatedOutput]);
+    }
+
+    #[test]
+    fn extracts_array_before_object() {
+        let repaired =
+            repair_json_value("Changes:\n[{\"op\": \"remove\", \"path\": \"/lore/0\"},]").unwrap();
+        assert_eq!(repaired.value, json!([{"op": "remove", "path": "/lore/0"}]));
+        assert_eq!(
+            repaired.repairs,
+            vec![
+                Repair::StrippedSurroundingText,
+                Repair::RemovedTrailingCommas(1)
+            ]
+        );
+    }
+
+    #[test]
+    fn fails_without_object() {
+        assert!(matches!(
+            repair_json("I cannot help with that."),
+            Err(RepairError::NoObject)
+        ));
+    }
+}
@@ -0,0 +1,215 @@
+use log::warn;
+use rig::completion::Message;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Conversation of a character, saved next to it so iterations can resume across runs.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Session {
+    pub name: String,
+    pub created_at: String,
+    pub updated_at: String,
+    /// Every exchanged message, oldest first.
+    pub history: Vec<Message>,
+    #[serde(skip)]
+    pub path: String,
+}
+
+impl Session {
+    /// An empty session saved as `<dir>/<name>.json`.
+    pub fn new(dir: &str, name: &str) -> Self {
+        let now = chrono::Local::now().to_rfc3339();
+        Session {
+            name: name.to_string(),
+            created_at: now.clone(),
+            updated_at: now,
+            history: vec![],
+            path: Self::path(dir, name),
+        }
+    }
+
+    pub fn path(dir: &str, name: &str) -> String {
+        format!("{}/{}.json", dir, name)
+    }
+
+    /// Session name of the character saved as `output_file_name`.
+    pub fn name_for(output_file_name: &str) -> String {
+        output_file_name.trim_end_matches(".json").to_string()
+    }
+
+    pub fn load(dir: &str, name: &str) -> Result<Self, anyhow::Error> {
+        let path = Self::path(dir, name);
+        let mut session: Session = serde_json::from_str(&fs::read_to_string(&path)?)?;
+        session.path = path;
+        Ok(session)
+    }
+
+    /// Resumes the saved session, or starts an empty one when there is none.
+    pub fn load_or_new(dir: &str, name: &str) -> Result<Self, anyhow::Error> {
+        match Path::new(&Self::path(dir, name)).exists() {
+            true => Self::load(dir, name),
+            false => Ok(Self::new(dir, name)),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), anyhow::Error> {
+        if let Some(parent) = Path::new(&self.path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn push(&mut self, role: &str, content: String) {
+        self.history.push(Message {
+            role: role.to_string(),
+            content,
+        });
+        self.updated_at = chrono::Local::now().to_rfc3339();
+    }
+
+    /// The latest `size` messages, oldest first.
+    pub fn recent(&self, size: usize) -> &[Message] {
+        &self.history[self.history.len().saturating_sub(size)..]
+    }
+
+    /// Every session saved in `dir`, sorted by name. Unreadable sessions are skipped with a warning.
+    pub fn list(dir: &str) -> Result<Vec<Session>, anyhow::Error> {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Ok(vec![]);
+        };
+        let mut sessions = vec![];
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let name = path.file_stem().unwrap_or_default().to_string_lossy();
+            match Self::load(dir, &name) {
+                Ok(session) => sessions.push(session),
+                Err(e) => warn!("[CHARGEN] Skipping session {}: {}", name, e),
+            }
+        }
+        sessions.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(sessions)
+    }
+
+    /// Copies the session `from` to a new session `to`.
+    pub fn fork(dir: &str, from: &str, to: &str) -> Result<Self, anyhow::Error> {
+        if Path::new(&Self::path(dir, to)).exists() {
+            return Err(anyhow::anyhow!("Session `{}` already exists", to));
+        }
+        let mut session = Self::load(dir, from)
+            .map_err(|e| anyhow::anyhow!("Failed to load session `{}`: {}", from, e))?;
+        session.name = to.to_string();
+        session.path = Self::path(dir, to);
+        session.created_at = chrono::Local::now().to_rfc3339();
+        session.save()?;
+        Ok(session)
+    }
+
+    /// Deletes the session `name`, the character itself is kept.
+    pub fn clear(dir: &str, name: &str) -> Result<(), anyhow::Error> {
+        fs::remove_file(Self::path(dir, name))
+            .map_err(|e| anyhow::anyhow!("Failed to clear session `{}`: {}", name, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dir() -> (tempfile::TempDir, String) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sessions").to_string_lossy().to_string();
+        (dir, path)
+    }
+
+    #[test]
+    fn saved_session_is_resumed() {
+        let (_tmp, dir) = dir();
+        let mut session = Session::load_or_new(&dir, "ayla").unwrap();
+        assert!(session.history.is_empty());
+        session.push("user", "make her older".to_string());
+        session.push("assistant", "{}".to_string());
+        session.save().unwrap();
+
+        let resumed = Session::load_or_new(&dir, "ayla").unwrap();
+        assert_eq!(resumed.name, "ayla");
+        assert_eq!(resumed.path, Session::path(&dir, "ayla"));
+        assert_eq!(resumed.created_at, session.created_at);
+        let messages: Vec<_> = resumed
+            .history
+            .iter()
+            .map(|m| (m.role.as_str(), m.content.as_str()))
+            .collect();
+        assert_eq!(messages, [("user", "make her older"), ("assistant", "{}")]);
+    }
+
+    #[test]
+    fn recent_keeps_the_latest_messages() {
+        let mut session = Session::new("sessions", "ayla");
+        for i in 0..5 {
+            session.push("user", i.to_string());
+        }
+        let recent: Vec<_> = session
+            .recent(2)
+            .iter()
+            .map(|m| m.content.as_str())
+            .collect();
+        assert_eq!(recent, ["3", "4"]);
+        assert_eq!(session.recent(10).len(), 5);
+        assert!(session.recent(0).is_empty());
+    }
+
+    #[test]
+    fn fork_refuses_an_existing_session() {
+        let (_tmp, dir) = dir();
+        let mut session = Session::new(&dir, "ayla");
+        session.push("user", "make her older".to_string());
+        session.save().unwrap();
+        Session::new(&dir, "taken").save().unwrap();
+
+        let error = Session::fork(&dir, "ayla", "taken").unwrap_err();
+        assert_eq!(error.to_string(), "Session `taken` already exists");
+        assert!(Session::load(&dir, "taken").unwrap().history.is_empty());
+
+        let fork = Session::fork(&dir, "ayla", "darker").unwrap();
+        assert_eq!(fork.name, "darker");
+        assert_eq!(Session::load(&dir, "darker").unwrap().history.len(), 1);
+    }
+
+    #[test]
+    fn clear_removes_only_the_session() {
+        let (_tmp, dir) = dir();
+        Session::new(&dir, "ayla").save().unwrap();
+        Session::new(&dir, "bram").save().unwrap();
+
+        Session::clear(&dir, "ayla").unwrap();
+        assert!(!Path::new(&Session::path(&dir, "ayla")).exists());
+        assert!(Session::clear(&dir, "ayla").is_err());
+        let names: Vec<_> = Session::list(&dir)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        assert_eq!(names, ["bram"]);
+    }
+
+    #[test]
+    fn list_skips_unreadable_sessions() {
+        let (_tmp, dir) = dir();
+        Session::new(&dir, "bram").save().unwrap();
+        Session::new(&dir, "ayla").save().unwrap();
+        fs::write(Session::path(&dir, "broken"), "{").unwrap();
+        fs::write(format!("{}/notes.txt", dir), "not a session").unwrap();
+
+        let names: Vec<_> = Session::list(&dir)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        assert_eq!(names, ["ayla", "bram"]);
+    }
+}
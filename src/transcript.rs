@@ -0,0 +1,79 @@
+use rig::completion::{CompletionRequest, Document, Message};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+
+/// One provider call: the request as sent and the text (or error) that came back.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TranscriptEntry {
+    pub timestamp: String,
+    pub preamble: Option<String>,
+    pub prompt: String,
+    pub documents: Vec<Document>,
+    pub history: Vec<Message>,
+    #[serde(default)]
+    pub response: Option<String>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl TranscriptEntry {
+    pub fn new(request: &CompletionRequest, result: Result<&str, String>) -> Self {
+        let (response, error) = match result {
+            Ok(text) => (Some(text.to_string()), None),
+            Err(e) => (None, Some(e)),
+        };
+        TranscriptEntry {
+            timestamp: chrono::Local::now().to_rfc3339(),
+            preamble: request.preamble.clone(),
+            prompt: request.prompt.clone(),
+            documents: request.documents.clone(),
+            history: request.chat_history.clone(),
+            response,
+            error,
+        }
+    }
+}
+
+/// Append-only JSONL log of every completion request and response.
+#[derive(Clone, Debug)]
+pub struct Transcript {
+    pub path: String,
+}
+
+impl Transcript {
+    /// Starts a new transcript in `dir` for the character saved as `output_file_name`.
+    pub fn create(dir: &str, output_file_name: &str) -> Result<Self, anyhow::Error> {
+        fs::create_dir_all(dir)?;
+        let stem = output_file_name.trim_end_matches(".json");
+        let path = format!(
+            "{}/{}-{}.jsonl",
+            dir,
+            stem,
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        );
+        Ok(Transcript { path })
+    }
+
+    /// Appends `entry` as one JSON line.
+    pub fn record(&self, entry: &TranscriptEntry) -> Result<(), anyhow::Error> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(entry)?)?;
+        Ok(())
+    }
+
+    /// Reads every entry of the transcript at `path`.
+    pub fn load(path: &str) -> Result<Vec<TranscriptEntry>, anyhow::Error> {
+        let mut entries = vec![];
+        for line in fs::read_to_string(path)?.lines() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            entries.push(serde_json::from_str(line)?);
+        }
+        Ok(entries)
+    }
+}
@@ -0,0 +1,386 @@
+use crate::character::Character;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Snapshot of a saved character and what produced it.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Version {
+    pub id: usize,
+    /// Version the instruction was applied to, `None` for the first one.
+    pub parent: Option<usize>,
+    pub timestamp: String,
+    pub instruction: String,
+    pub provider: String,
+    pub model: String,
+    pub character: Character,
+}
+
+/// An independent line of iteration through the version tree.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Branch {
+    /// Version currently saved as the branch's character.
+    pub head: usize,
+    /// Versions left by `undo`, most recent last.
+    #[serde(default)]
+    pub redo: Vec<usize>,
+    /// Version the branch was forked at, `None` for the main branch.
+    #[serde(default)]
+    pub forked_from: Option<usize>,
+}
+
+/// Every saved iteration of a character, stored in `<output_dir>/versions/<name>.json`.
+#[derive(Serialize, Deserialize, Default)]
+pub struct VersionHistory {
+    pub versions: Vec<Version>,
+    #[serde(default)]
+    pub branches: BTreeMap<String, Branch>,
+    #[serde(skip)]
+    pub path: String,
+}
+
+impl VersionHistory {
+    /// Branch saved as the character's output file.
+    pub const MAIN: &'static str = "main";
+
+    pub fn path(dir: &str, output_file_name: &str) -> String {
+        format!("{}/{}", dir, output_file_name)
+    }
+
+    /// Loads the history of the character saved as `output_file_name`, empty when there is none.
+    pub fn load(dir: &str, output_file_name: &str) -> Result<Self, anyhow::Error> {
+        let path = Self::path(dir, output_file_name);
+        let mut history = match Path::new(&path).exists() {
+            true => serde_json::from_str(&fs::read_to_string(&path)?)?,
+            false => VersionHistory::default(),
+        };
+        history.path = path;
+        Ok(history)
+    }
+
+    pub fn save(&self) -> Result<(), anyhow::Error> {
+        if let Some(parent) = Path::new(&self.path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, id: usize) -> Option<&Version> {
+        self.versions.iter().find(|version| version.id == id)
+    }
+
+    pub fn head(&self, branch: &str) -> Option<&Version> {
+        self.branches
+            .get(branch)
+            .and_then(|branch| self.get(branch.head))
+    }
+
+    fn branch_mut(&mut self, name: &str) -> Result<&mut Branch, anyhow::Error> {
+        self.branches
+            .get_mut(name)
+            .ok_or_else(|| anyhow::anyhow!("No saved versions on branch `{}`", name))
+    }
+
+    /// Snapshots `character` as a child of the branch head and moves the head to it.
+    pub fn record(
+        &mut self,
+        branch: &str,
+        character: &Character,
+        instruction: &str,
+        provider: &str,
+        model: &str,
+    ) -> usize {
+        let id = self
+            .versions
+            .iter()
+            .map(|version| version.id + 1)
+            .max()
+            .unwrap_or(1);
+        self.versions.push(Version {
+            id,
+            parent: self.branches.get(branch).map(|branch| branch.head),
+            timestamp: chrono::Local::now().to_rfc3339(),
+            instruction: instruction.to_string(),
+            provider: provider.to_string(),
+            model: model.to_string(),
+            character: character.clone(),
+        });
+
+        let branch = self.branches.entry(branch.to_string()).or_insert(Branch {
+            head: id,
+            redo: vec![],
+            forked_from: None,
+        });
+        branch.head = id;
+        branch.redo.clear();
+        id
+    }
+
+    /// Moves the branch head back to its parent.
+    pub fn undo(&mut self, branch: &str) -> Result<&Version, anyhow::Error> {
+        let head = self
+            .head(branch)
+            .ok_or_else(|| anyhow::anyhow!("No saved versions on branch `{}`", branch))?;
+        let (id, parent) = (head.id, head.parent);
+        let parent = parent
+            .ok_or_else(|| anyhow::anyhow!("Nothing to undo, version {} is the first", id))?;
+
+        let branch_ref = self.branch_mut(branch)?;
+        branch_ref.redo.push(id);
+        branch_ref.head = parent;
+        Ok(self.get(parent).expect("parent version exists"))
+    }
+
+    /// Moves the branch head forward to the version last left by [`VersionHistory::undo`].
+    pub fn redo(&mut self, branch: &str) -> Result<&Version, anyhow::Error> {
+        let branch_ref = self.branch_mut(branch)?;
+        let id = branch_ref
+            .redo
+            .pop()
+            .ok_or_else(|| anyhow::anyhow!("Nothing to redo"))?;
+        branch_ref.head = id;
+        self.get(id)
+            .ok_or_else(|| anyhow::anyhow!("Version {} not found", id))
+    }
+
+    /// Moves the branch head to any version.
+    pub fn checkout(&mut self, branch: &str, id: usize) -> Result<&Version, anyhow::Error> {
+        if self.get(id).is_none() {
+            return Err(anyhow::anyhow!("Version {} not found", id));
+        }
+        let branch_ref = self.branch_mut(branch)?;
+        branch_ref.head = id;
+        branch_ref.redo.clear();
+        Ok(self.get(id).expect("checked out version exists"))
+    }
+
+    /// Starts a branch `name` at version `id`.
+    pub fn fork(&mut self, name: &str, id: usize) -> Result<&Version, anyhow::Error> {
+        // branch names end up in file names
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(anyhow::anyhow!(
+                "Branch names may only contain letters, digits, `-` and `_`"
+            ));
+        }
+        if self.branches.contains_key(name) {
+            return Err(anyhow::anyhow!("Branch `{}` already exists", name));
+        }
+        if self.get(id).is_none() {
+            return Err(anyhow::anyhow!("Version {} not found", id));
+        }
+        self.branches.insert(
+            name.to_string(),
+            Branch {
+                head: id,
+                redo: vec![],
+                forked_from: Some(id),
+            },
+        );
+        Ok(self.get(id).expect("forked version exists"))
+    }
+
+    /// Moves the main branch head to the head of `branch`. The previous main head stays in the
+    /// history and can be restored with [`VersionHistory::checkout`].
+    pub fn promote(&mut self, branch: &str) -> Result<&Version, anyhow::Error> {
+        let id = self
+            .branches
+            .get(branch)
+            .map(|branch| branch.head)
+            .ok_or_else(|| anyhow::anyhow!("Branch `{}` not found", branch))?;
+        match self.branches.get_mut(Self::MAIN) {
+            Some(main) => {
+                main.head = id;
+                main.redo.clear();
+            }
+            None => {
+                self.branches.insert(
+                    Self::MAIN.to_string(),
+                    Branch {
+                        head: id,
+                        redo: vec![],
+                        forked_from: None,
+                    },
+                );
+            }
+        }
+        Ok(self.get(id).expect("branch head exists"))
+    }
+
+    /// Forks branch `name` at version `at`, the head of `from` by default, and saves that version
+    /// as the branch's character at `path`.
+    pub fn fork_to(
+        &mut self,
+        from: &str,
+        name: &str,
+        at: Option<usize>,
+        path: String,
+    ) -> Result<Version, anyhow::Error> {
+        let at = match at {
+            Some(id) => id,
+            None => self
+                .head(from)
+                .map(|version| version.id)
+                .ok_or_else(|| anyhow::anyhow!("No saved versions on branch `{}`", from))?,
+        };
+        self.fork(name, at)?;
+        self.restore(at, path)
+    }
+
+    /// Promotes `branch` and saves its head as the main character at `path`.
+    pub fn promote_to(&mut self, branch: &str, path: String) -> Result<Version, anyhow::Error> {
+        let id = self.promote(branch)?.id;
+        self.restore(id, path)
+    }
+
+    /// Saves version `id` as the character at `path`, then the history with its moved heads.
+    pub fn restore(&self, id: usize, path: String) -> Result<Version, anyhow::Error> {
+        let mut version = self
+            .get(id)
+            .ok_or_else(|| anyhow::anyhow!("Version {} not found", id))?
+            .clone();
+        version.character.path = path;
+        version
+            .character
+            .save()
+            .map_err(|e| anyhow::anyhow!("Failed to save character: {}", e))?;
+        self.save()?;
+        Ok(version)
+    }
+
+    /// Branches whose head is version `id`.
+    pub fn branches_at(&self, id: usize) -> Vec<&str> {
+        self.branches
+            .iter()
+            .filter(|(_, branch)| branch.head == id)
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn character(bio: &str) -> Character {
+        let mut character = Character::new(String::new());
+        character.alias = "Ayla".to_string();
+        character.bio = bio.to_string();
+        character
+    }
+
+    /// Main branch with versions 1 <- 2 <- 3.
+    fn history() -> VersionHistory {
+        let mut history = VersionHistory::default();
+        for bio in ["first", "second", "third"] {
+            history.record(VersionHistory::MAIN, &character(bio), bio, "mock", "script");
+        }
+        history
+    }
+
+    fn head(history: &VersionHistory, branch: &str) -> usize {
+        history.head(branch).unwrap().id
+    }
+
+    #[test]
+    fn records_versions_as_children_of_the_head() {
+        let history = history();
+        let parents: Vec<Option<usize>> = history.versions.iter().map(|v| v.parent).collect();
+        assert_eq!(parents, vec![None, Some(1), Some(2)]);
+        assert_eq!(head(&history, VersionHistory::MAIN), 3);
+    }
+
+    #[test]
+    fn undoes_and_redoes_along_parents() {
+        let mut history = history();
+        assert_eq!(history.undo(VersionHistory::MAIN).unwrap().id, 2);
+        assert_eq!(history.undo(VersionHistory::MAIN).unwrap().id, 1);
+        assert!(history.undo(VersionHistory::MAIN).is_err());
+
+        assert_eq!(history.redo(VersionHistory::MAIN).unwrap().id, 2);
+        assert_eq!(history.redo(VersionHistory::MAIN).unwrap().id, 3);
+        assert!(history.redo(VersionHistory::MAIN).is_err());
+    }
+
+    #[test]
+    fn recording_after_undo_clears_redo() {
+        let mut history = history();
+        history.undo(VersionHistory::MAIN).unwrap();
+        let id = history.record(VersionHistory::MAIN, &character("other"), "other", "", "");
+        assert_eq!(id, 4);
+        assert_eq!(history.get(4).unwrap().parent, Some(2));
+        assert!(history.redo(VersionHistory::MAIN).is_err());
+    }
+
+    #[test]
+    fn checks_out_any_version() {
+        let mut history = history();
+        history.undo(VersionHistory::MAIN).unwrap();
+        assert_eq!(history.checkout(VersionHistory::MAIN, 1).unwrap().id, 1);
+        assert!(history.redo(VersionHistory::MAIN).is_err());
+        assert!(history.checkout(VersionHistory::MAIN, 9).is_err());
+        assert!(history.undo("missing").is_err());
+    }
+
+    #[test]
+    fn forks_branches_with_their_own_heads() {
+        let mut history = history();
+        assert_eq!(history.fork("darker", 2).unwrap().id, 2);
+        assert_eq!(history.branches["darker"].forked_from, Some(2));
+
+        let id = history.record("darker", &character("darker"), "darker", "", "");
+        assert_eq!(history.get(id).unwrap().parent, Some(2));
+        assert_eq!(head(&history, "darker"), id);
+        assert_eq!(head(&history, VersionHistory::MAIN), 3);
+
+        assert!(history.fork("darker", 1).is_err());
+        assert!(history.fork("no spaces", 1).is_err());
+        assert!(history.fork("../escape", 1).is_err());
+        assert!(history.fork("later", 9).is_err());
+    }
+
+    #[test]
+    fn promotes_branch_head_to_main() {
+        let mut history = history();
+        history.fork("darker", 1).unwrap();
+        let id = history.record("darker", &character("darker"), "darker", "", "");
+        assert_eq!(history.promote("darker").unwrap().id, id);
+        assert_eq!(head(&history, VersionHistory::MAIN), id);
+        // the previous main head is kept and can be checked out again
+        assert_eq!(history.checkout(VersionHistory::MAIN, 3).unwrap().id, 3);
+        assert!(history.promote("missing").is_err());
+    }
+
+    #[test]
+    fn fork_to_and_promote_to_save_the_character() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path().to_string_lossy();
+        let mut history = history();
+        history.path = VersionHistory::path(&format!("{}/versions", dir), "ayla.json");
+
+        let branch_path = format!("{}/branches/darker.json", dir);
+        let version = history
+            .fork_to(VersionHistory::MAIN, "darker", None, branch_path.clone())
+            .unwrap();
+        assert_eq!(version.id, 3);
+        let mut saved = Character::new(branch_path);
+        saved.load().unwrap();
+        assert_eq!(saved.bio, "third");
+
+        let main_path = format!("{}/characters/ayla.json", dir);
+        history.checkout("darker", 1).unwrap();
+        let version = history.promote_to("darker", main_path.clone()).unwrap();
+        assert_eq!(version.id, 1);
+        let mut saved = Character::new(main_path);
+        saved.load().unwrap();
+        assert_eq!(saved.bio, "first");
+
+        let reloaded = VersionHistory::load(&format!("{}/versions", dir), "ayla.json").unwrap();
+        assert_eq!(head(&reloaded, VersionHistory::MAIN), 1);
+    }
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use common::{character, stderr, Fixture};
+use serde_json::{json, Value};
+use std::fs;
+
+#[test]
+fn generates_a_manifest_and_resumes_after_failures() {
+    let mut bo = character("A chef.");
+    bo["alias"] = json!("Bo Jin");
+    let fixture = Fixture::new(
+        json!({}),
+        json!({
+            "rules": [
+                {"pattern": "Use Ayla as the alias", "response": character("A pilot.")},
+                {"pattern": "Use Bo Jin as the alias", "response": bo},
+            ],
+            "responses": [],
+        }),
+    );
+    fs::write(
+        fixture.dir.path().join("cast.csv"),
+        "name,facts,output\nAyla,pilot,\nBo Jin,chef|cook,bo.json\nCy,unscripted,\n",
+    )
+    .unwrap();
+
+    let output = fixture.run(&["batch", "cast.csv", "--jobs", "2"]);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr(&output));
+    assert_eq!(fixture.character("ayla.json")["bio"], "A pilot.");
+    assert_eq!(fixture.character("bo.json")["alias"], "Bo Jin");
+
+    let report: Value = serde_json::from_str(
+        &fs::read_to_string(fixture.dir.path().join("out/batch/cast.report.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(report["results"]["ayla.json"]["status"], "succeeded");
+    assert_eq!(report["results"]["bo.json"]["status"], "succeeded");
+    assert_eq!(report["results"]["cy.json"]["status"], "failed");
+
+    // the rerun only retries the failure
+    let output = fixture.run(&["batch", "cast.csv"]);
+    let log = stderr(&output);
+    assert!(log.contains("Skipping Ayla, already generated"), "{}", log);
+    assert!(
+        log.contains("Skipping Bo Jin, already generated"),
+        "{}",
+        log
+    );
+    assert!(log.contains("Generating 1 of 3 characters"), "{}", log);
+}
+
+#[test]
+fn rejects_outputs_outside_the_characters_directory() {
+    let fixture = Fixture::new(json!({}), json!({"responses": []}));
+    fs::write(
+        fixture.dir.path().join("cast.json"),
+        r#"[{"name": "Ayla", "output": "../../escape"}]"#,
+    )
+    .unwrap();
+    let output = fixture.run(&["batch", "cast.json"]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("without path separators"));
+}
@@ -0,0 +1,69 @@
+use serde_json::{json, Value};
+use std::fs;
+use std::process::{Command, Output, Stdio};
+use tempfile::TempDir;
+
+/// A working directory with `config.json`, `input.json` and a `mock.json` script, in which the
+/// CLI is run like a user would.
+pub struct Fixture {
+    pub dir: TempDir,
+}
+
+impl Fixture {
+    /// `config` is merged into a config using the mock provider and saving `ayla.json`.
+    pub fn new(config: Value, mock: Value) -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let mut base = json!({
+            "completion_provider": "mock",
+            "output_file_name": "ayla.json",
+            "validation_retries": 1,
+        });
+        json_patch::merge(&mut base, &config);
+        fs::write(dir.path().join("config.json"), base.to_string()).unwrap();
+        fs::write(
+            dir.path().join("input.json"),
+            json!({"name": "Ayla", "facts": ["pilot"], "files": []}).to_string(),
+        )
+        .unwrap();
+        fs::write(dir.path().join("mock.json"), mock.to_string()).unwrap();
+        fs::create_dir(dir.path().join("in")).unwrap();
+        Fixture { dir }
+    }
+
+    pub fn command(&self) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_fabelis-characterfile"));
+        command
+            .current_dir(self.dir.path())
+            .env("RUST_BACKTRACE", "0")
+            .env_remove("MOCK_SCRIPT_PATH")
+            .env_remove("REPLAY_TRANSCRIPT_PATH")
+            .stdin(Stdio::null());
+        command
+    }
+
+    pub fn run(&self, args: &[&str]) -> Output {
+        self.command().args(args).output().unwrap()
+    }
+
+    /// The character saved under `out/characters/`.
+    pub fn character(&self, file_name: &str) -> Value {
+        let path = self.dir.path().join("out/characters").join(file_name);
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+}
+
+pub fn character(bio: &str) -> Value {
+    json!({
+        "alias": "Ayla",
+        "bio": bio,
+        "adjectives": ["bold"],
+        "lore": ["flew forty missions"],
+        "styles": ["terse"],
+        "topics": ["aviation"],
+        "inspirations": [],
+    })
+}
+
+pub fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use common::{character, stderr, Fixture};
+use serde_json::json;
+use std::fs;
+use std::path::PathBuf;
+
+/// Runs `instructions` against `mock` with transcripts on and returns the recorded transcript.
+fn record(mock: serde_json::Value, instructions: &[&str]) -> (Fixture, PathBuf) {
+    let fixture = Fixture::new(json!({"record_transcripts": true}), mock);
+    let mut args = vec!["run"];
+    for instruction in instructions {
+        args.extend(["-m", instruction]);
+    }
+    fixture.run(&args);
+
+    let transcripts: Vec<PathBuf> = fs::read_dir(fixture.dir.path().join("out/transcripts"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    assert_eq!(transcripts.len(), 1);
+    let transcript = transcripts[0].clone();
+    (fixture, transcript)
+}
+
+#[test]
+fn replays_a_recorded_run() {
+    let (recorded, transcript) = record(
+        json!({"responses": [character("A pilot."), character("A daring pilot.")]}),
+        &["create her", "make her daring"],
+    );
+
+    let replay = Fixture::new(json!({"completion_provider": "replay"}), json!({}));
+    let output = replay
+        .command()
+        .args(["run", "-m", "create her", "-m", "make her daring"])
+        .env("REPLAY_TRANSCRIPT_PATH", &transcript)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        replay.character("ayla.json"),
+        recorded.character("ayla.json")
+    );
+}
+
+#[test]
+fn replays_recorded_errors() {
+    let (_recorded, transcript) = record(json!({"responses": []}), &["create her"]);
+
+    let replay = Fixture::new(json!({"completion_provider": "replay"}), json!({}));
+    let output = replay
+        .command()
+        .args(["run", "-m", "create her"])
+        .env("REPLAY_TRANSCRIPT_PATH", &transcript)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(3), "{}", stderr(&output));
+    assert!(stderr(&output).contains("Mock script exhausted"));
+}
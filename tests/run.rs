@@ -0,0 +1,81 @@
+mod common;
+
+use common::{character, stderr, Fixture};
+use serde_json::json;
+
+#[test]
+fn creates_then_iterates_and_prints_the_path() {
+    let fixture = Fixture::new(
+        json!({}),
+        json!({"responses": [character("A pilot."), character("A daring pilot.")]}),
+    );
+    let output = fixture.run(&["run", "-m", "create her", "-m", "make her daring"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "out/characters/ayla.json"
+    );
+    assert_eq!(fixture.character("ayla.json")["bio"], "A daring pilot.");
+}
+
+#[test]
+fn repairs_fenced_response_with_trailing_commas() {
+    let response = "Sure! Here is Ayla:\n```json\n{\"alias\": \"Ayla\", \"bio\": \"A pilot.\", \"adjectives\": [\"bold\",], \"lore\": [], \"styles\": [], \"topics\": [], \"inspirations\": [],}\n```";
+    let fixture = Fixture::new(json!({}), json!({"responses": [response]}));
+    let output = fixture.run(&["run", "-m", "create her"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stderr(&output).contains("Repaired response"));
+    assert_eq!(
+        fixture.character("ayla.json")["adjectives"],
+        json!(["bold"])
+    );
+}
+
+#[test]
+fn reprompts_until_the_character_validates() {
+    let fixture = Fixture::new(
+        json!({}),
+        json!({
+            "rules": [{"pattern": "could not be used", "response": character("A pilot.")}],
+            "responses": [{"alias": "Ayla", "bio": 3}],
+        }),
+    );
+    let output = fixture.run(&["run", "-m", "create her"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(fixture.character("ayla.json")["bio"], "A pilot.");
+}
+
+#[test]
+fn exits_4_when_the_character_never_validates() {
+    let fixture = Fixture::new(
+        json!({"validation_retries": 1}),
+        json!({"rules": [{"pattern": ".", "response": {"alias": "Ayla"}}]}),
+    );
+    let output = fixture.run(&["run", "-m", "create her"]);
+    assert_eq!(output.status.code(), Some(4), "{}", stderr(&output));
+    assert!(stderr(&output).contains("failed validation"));
+    assert!(!fixture.dir.path().join("out/characters/ayla.json").exists());
+}
+
+#[test]
+fn exits_4_on_patches_touching_locked_fields() {
+    let fixture = Fixture::new(
+        json!({"locked_fields": ["bio"], "validation_retries": 0}),
+        json!({
+            "rules": [{"pattern": "rewrite", "response": [{"op": "replace", "path": "/bio", "value": "x"}]}],
+            "responses": [character("A pilot.")],
+        }),
+    );
+    assert!(fixture.run(&["run", "-m", "create her"]).status.success());
+    let output = fixture.run(&["run", "--patch", "-m", "rewrite the bio"]);
+    assert_eq!(output.status.code(), Some(4), "{}", stderr(&output));
+    assert_eq!(fixture.character("ayla.json")["bio"], "A pilot.");
+}
+
+#[test]
+fn exits_3_when_the_provider_fails() {
+    let fixture = Fixture::new(json!({}), json!({"responses": []}));
+    let output = fixture.run(&["run", "-m", "create her"]);
+    assert_eq!(output.status.code(), Some(3), "{}", stderr(&output));
+    assert!(stderr(&output).contains("Mock script exhausted"));
+}